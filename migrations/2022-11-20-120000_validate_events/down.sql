@@ -0,0 +1,16 @@
+-- This file should undo anything in `up.sql`
+
+CREATE TABLE events_old(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    day TEXT NOT NULL,
+    starth TEXT NOT NULL,
+    endh TEXT NOT NULL,
+    isLecture INTEGER NOT NULL DEFAULT FALSE
+);
+
+INSERT INTO events_old (id, name, day, starth, endh, isLecture)
+    SELECT id, name, day, starth, endh, isLecture FROM events;
+
+DROP TABLE events;
+ALTER TABLE events_old RENAME TO events;
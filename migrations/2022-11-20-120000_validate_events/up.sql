@@ -0,0 +1,34 @@
+-- SQLite can't add constraints to an existing table, so the table is rebuilt.
+-- Rows that already violate the constraints are repaired before they are copied,
+-- otherwise this migration would fail and syd couldn't open the database at all:
+-- blank names become `Unnamed event`, swapped hours are put back in order and
+-- events ending when they start last an hour, or until the end of the day.
+-- Only events starting at 23:59:59 with no length can't be repaired and are dropped.
+
+UPDATE events SET name = 'Unnamed event' WHERE length(trim(name)) = 0;
+
+UPDATE events SET starth = endh, endh = starth WHERE endh < starth;
+
+UPDATE events SET endh = CASE
+        WHEN starth >= '23:00:00' THEN '23:59:59'
+        ELSE time(starth, '+1 hour')
+    END
+    WHERE endh = starth;
+
+DELETE FROM events WHERE endh <= starth;
+
+CREATE TABLE events_new(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL CHECK (length(trim(name)) > 0),
+    day TEXT NOT NULL,
+    starth TEXT NOT NULL,
+    endh TEXT NOT NULL,
+    isLecture INTEGER NOT NULL DEFAULT FALSE,
+    CHECK (endh > starth)
+);
+
+INSERT INTO events_new (id, name, day, starth, endh, isLecture)
+    SELECT id, name, day, starth, endh, isLecture FROM events;
+
+DROP TABLE events;
+ALTER TABLE events_new RENAME TO events;
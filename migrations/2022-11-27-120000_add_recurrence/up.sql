@@ -0,0 +1,3 @@
+-- `weekly`, `odd`, `even` or `every:<weeks>:<anchor date>`
+
+ALTER TABLE events ADD COLUMN recurrence TEXT NOT NULL DEFAULT 'weekly';
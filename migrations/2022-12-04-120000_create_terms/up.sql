@@ -0,0 +1,12 @@
+-- Your SQL goes here
+
+CREATE TABLE terms(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE CHECK (length(trim(name)) > 0),
+    start_date TEXT NOT NULL,
+    end_date TEXT NOT NULL,
+    active INTEGER NOT NULL DEFAULT FALSE,
+    CHECK (end_date >= start_date)
+);
+
+ALTER TABLE events ADD COLUMN term_id INTEGER REFERENCES terms(id) ON DELETE SET NULL;
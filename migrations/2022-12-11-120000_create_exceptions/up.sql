@@ -0,0 +1,19 @@
+-- Your SQL goes here
+
+CREATE TABLE exceptions(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    date TEXT NOT NULL,
+    cancelled INTEGER NOT NULL DEFAULT FALSE,
+    starth TEXT,
+    endh TEXT,
+    room TEXT,
+    UNIQUE (event_id, date),
+    CHECK (starth IS NULL OR endh IS NULL OR endh > starth)
+);
+
+CREATE TABLE holidays(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    date TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL CHECK (length(trim(name)) > 0)
+);
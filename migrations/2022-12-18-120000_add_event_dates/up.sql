@@ -0,0 +1,3 @@
+-- Your SQL goes here
+
+ALTER TABLE events ADD COLUMN date TEXT;
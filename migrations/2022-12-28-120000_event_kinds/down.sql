@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE events ADD COLUMN isLecture INTEGER NOT NULL DEFAULT 0;
+UPDATE events SET isLecture = 1 WHERE kind = 'lecture';
+ALTER TABLE events DROP COLUMN kind;
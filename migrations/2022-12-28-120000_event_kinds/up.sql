@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+ALTER TABLE events ADD COLUMN kind TEXT NOT NULL DEFAULT 'other'
+    CHECK (kind IN ('lecture', 'lab', 'seminar', 'exercise', 'exam', 'other'));
+UPDATE events SET kind = 'lecture' WHERE isLecture != 0;
+ALTER TABLE events DROP COLUMN isLecture;
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+
+CREATE TABLE rooms(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    building TEXT NOT NULL CHECK (length(trim(building)) > 0),
+    number TEXT NOT NULL CHECK (length(trim(number)) > 0),
+    notes TEXT,
+    UNIQUE (building, number)
+);
+
+ALTER TABLE events ADD COLUMN room_id INTEGER REFERENCES rooms(id) ON DELETE SET NULL;
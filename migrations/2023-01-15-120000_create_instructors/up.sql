@@ -0,0 +1,15 @@
+-- Your SQL goes here
+
+CREATE TABLE instructors(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE CHECK (length(trim(name)) > 0),
+    email TEXT,
+    office TEXT,
+    office_hours TEXT
+);
+
+CREATE TABLE event_instructors(
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    instructor_id INTEGER NOT NULL REFERENCES instructors(id) ON DELETE CASCADE,
+    PRIMARY KEY (event_id, instructor_id)
+);
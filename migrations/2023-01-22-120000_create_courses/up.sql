@@ -0,0 +1,11 @@
+-- Your SQL goes here
+
+CREATE TABLE courses(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    code TEXT NOT NULL UNIQUE CHECK (length(trim(code)) > 0),
+    title TEXT NOT NULL CHECK (length(trim(title)) > 0),
+    ects REAL NOT NULL DEFAULT 0 CHECK (ects >= 0),
+    colour TEXT
+);
+
+ALTER TABLE events ADD COLUMN course_id INTEGER REFERENCES courses(id) ON DELETE SET NULL;
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+
+CREATE TABLE event_tags(
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    tag TEXT NOT NULL COLLATE NOCASE CHECK (length(trim(tag)) > 0),
+    PRIMARY KEY (event_id, tag)
+);
+
+CREATE TABLE notes(
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_id INTEGER NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+    body TEXT NOT NULL CHECK (length(trim(body)) > 0)
+);
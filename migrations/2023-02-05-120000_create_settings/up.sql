@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+CREATE TABLE settings(
+    key TEXT PRIMARY KEY NOT NULL,
+    value TEXT NOT NULL
+);
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE exceptions ADD COLUMN room TEXT;
+UPDATE exceptions SET room = (
+    SELECT building || ' ' || number FROM rooms WHERE rooms.id = exceptions.room_id
+);
+ALTER TABLE exceptions DROP COLUMN room_id;
@@ -0,0 +1,23 @@
+-- Your SQL goes here
+
+ALTER TABLE exceptions ADD COLUMN room_id INTEGER REFERENCES rooms(id) ON DELETE SET NULL;
+
+-- rooms typed into exceptions are split at their last space, like `Room::from_str` does
+CREATE TEMPORARY TABLE exception_rooms AS
+    SELECT id,
+        rtrim(rtrim(trim(room), replace(trim(room), ' ', '')), ' ') AS building,
+        substr(trim(room), length(rtrim(trim(room), replace(trim(room), ' ', ''))) + 1) AS number
+    FROM exceptions
+    WHERE instr(trim(room), ' ') > 0;
+
+INSERT OR IGNORE INTO rooms(building, number)
+    SELECT DISTINCT building, number FROM exception_rooms;
+
+UPDATE exceptions SET room_id = (
+    SELECT rooms.id FROM exception_rooms
+    JOIN rooms ON rooms.building = exception_rooms.building AND rooms.number = exception_rooms.number
+    WHERE exception_rooms.id = exceptions.id
+);
+
+DROP TABLE exception_rooms;
+ALTER TABLE exceptions DROP COLUMN room;
@@ -1,28 +1,39 @@
-use std::{collections::{HashSet}, str::FromStr};
+use std::{collections::HashMap, str::FromStr};
 
 use anyhow::{Result, anyhow};
+use crate::render::{Renderer, OutputFormat};
  
 pub struct CommandHandler {
-    commands: HashSet<Command>,
-    manager: EventsManager
+    commands: CommandTree,
+    manager: EventsManager,
+    renderer: Box<dyn Renderer>,
 }
 
 impl CommandHandler {
     pub fn new(manager: EventsManager) -> Self {
         Self {
-            commands: HashSet::new(),
-            manager: manager
+            commands: CommandTree::default(),
+            manager,
+            renderer: OutputFormat::Text.renderer(),
         }
     }
+    ///Sets the renderer that `handle` uses to print the output of commands.
+    pub fn set_renderer(&mut self, renderer: Box<dyn Renderer>) {
+        self.renderer = renderer;
+    }
+    ///Shorthand for `set_renderer` with one of the built-in renderers.
+    pub fn set_output_format(&mut self, format: OutputFormat) {
+        self.renderer = format.renderer();
+    }
     pub fn add_command(&mut self, command: Command) -> Result<()> {
         let name = command.name.clone();
-        if name.contains(' ') {
+        if name.contains(' ') || command.group.iter().any(|g| g.is_empty() || g.contains(' ')) {
             return Err(anyhow::anyhow!("Invalid command name!"));
         }
-        if !self.commands.insert(command) {
-            return Err(anyhow::anyhow!("Command {} already registered!", &name));
+        if name == "help" && command.group.is_empty() {
+            return Err(anyhow::anyhow!("`help` is built in!"));
         }
-        Ok(())
+        self.commands.insert(command)
     }
     ///Registers commands from a type that implements `CommandModule`
     /// 
@@ -30,26 +41,33 @@ impl CommandHandler {
     pub fn add_module<T: CommandModule>(&mut self) -> Result<()> {
         let commands = T::init();
         for command in commands {
-            let command_name = command.name.clone();
-            let group = command.group.clone().unwrap_or("".to_owned());
-            if !self.commands.insert(command) {
-                return Err(anyhow!("command named: {} in group: {} already exists!", 
-                                command_name, group));
-            }
+            self.add_command(command)?;
         }
         Ok(())
     }
-    pub fn remove_command(&mut self, name: &str, group: Option<String>) -> Result<()> {
-        self.commands.remove(&Command {
-            name: name.into(),
-            desc: None,
-            group: group,
-            args: vec![],
-            function: Box::new(|_, _|{Ok(())})
-        });
+    pub fn remove_command(&mut self, name: &str, group: &[String]) -> Result<()> {
+        self.commands.remove(name, group);
         Ok(())
     }
+    ///The tree of registered commands and their groups.
+    pub fn commands(&self) -> &CommandTree {
+        &self.commands
+    }
+    ///Executes a command and prints its output using the current renderer.
     pub fn handle(&mut self, input: String) -> Result<()> {
+        let output = self.execute(input)?;
+        let rendered = self.render(&output)?;
+        if !rendered.is_empty() {
+            println!("{}", rendered);
+        }
+        Ok(())
+    }
+    ///Renders output the way `handle` prints it.
+    pub fn render(&self, output: &CommandOutput) -> Result<String> {
+        self.renderer.render(output)
+    }
+    ///Executes a command and returns its output without rendering it.
+    pub fn execute(&mut self, input: String) -> Result<CommandOutput> {
         let mut args = Self::parse_input(input)?;
         if args.is_empty() {
             return Err(anyhow::anyhow!("No arguments found in input stream!"));
@@ -57,44 +75,24 @@ impl CommandHandler {
         //DEBUG
         //println!("{:?}", args);
         //DEBUG 
-        let first = args.get(0)
-            .ok_or_else(|| anyhow!("First argument not found! (wtf?)"))?
-            .to_owned();
-
-        let valid = self.commands.iter()
-                    .filter(|c| c.group == Some(first.clone()))
-                    .collect::<Vec<&Command>>();
-
-        if !valid.is_empty() {
-            let name = args.get(1)
-                .ok_or_else(|| anyhow!("No function specified"))?
-                .to_owned();
-            let valid = valid.into_iter()
-                .filter(|c| c.name == name)
-                .collect::<Vec<&Command>>();
-
-            let command = valid.first()
-                                    .ok_or_else(|| anyhow!("Command not found!"))?;
-            args.remove(0);
-            args.remove(0);
-            let mut context = CommandContext {
-                manager: &mut self.manager,
-            };
-            (command.function)(&mut context, args)
-        } else {
-            let name = first;
-            let commands = self.commands.iter()
-                        .filter(|c| c.name == name)
-                        .collect::<Vec<&Command>>();
-            let command = commands.first()
-                        .ok_or_else(|| anyhow!("Command not found!"))?;
-            args.remove(0);
-
-            let mut context = CommandContext {
-                manager: &mut self.manager,
-            };
-            (command.function)(&mut context, args)
+        //after a lone `--` even `--help` is a plain value
+        let options = args.iter()
+            .position(|a| a == "--")
+            .unwrap_or(args.len());
+        if args[..options].iter().any(|a| a == "--help") {
+            args.truncate(options);
+            args.retain(|a| a != "--help");
+            return self.help(&args);
+        }
+        if args[0] == "help" {
+            return self.help(&args[1..]);
         }
+        let (command, depth) = self.commands.find(&args)?;
+        args.drain(..depth);
+        let mut context = CommandContext {
+            manager: &mut self.manager,
+        };
+        (command.function)(&mut context, args)
     }
 
     fn parse_input(input: String) -> Result<Vec<String>> {
@@ -130,63 +128,291 @@ impl CommandHandler {
         Ok(args)
     }
 
-    pub fn commands_info(&self) -> Vec<CommandInfo> {
-        let mut ret = vec![];
-        for cmd in &self.commands {
-            let desc = cmd.desc.as_ref()
-                .unwrap_or(&String::from(""))
-                .clone();
-            let group = cmd.group.clone();
-                
-            ret.push(CommandInfo {
-                name: &cmd.name.as_str(),
-                desc: desc,
-                group: group,
-                args: cmd.args.clone(),
-            });
+    ///Describes every registered command, sorted by group and name.
+    pub fn commands_info(&self) -> Vec<CommandInfo<'_>> {
+        let mut ret = self.commands.all()
+            .into_iter()
+            .map(CommandInfo::from)
+            .collect::<Vec<CommandInfo>>();
+        ret.sort_by(|a, b| (&a.group, a.name).cmp(&(&b.group, b.name)));
+        ret
+    }
+    ///Output of the built-in `help`, which is also what `<command> --help` runs.
+    /// 
+    /// Describes the command that `path` names, lists the commands below the group it names, or 
+    /// all commands when it's empty. Arguments after a command's name are ignored.
+    pub fn help(&self, path: &[String]) -> Result<CommandOutput> {
+        let mut node = &self.commands;
+        for (depth, name) in path.iter().enumerate() {
+            if let Some(group) = node.groups.get(name) {
+                node = group;
+            } else if let Some(command) = node.commands.get(name) {
+                return Ok(CommandInfo::from(command).help());
+            } else {
+                return Err(anyhow!("No command or group named `{}`", path[..=depth].join(" ")));
+            }
+        }
+        let mut commands = node.all()
+            .into_iter()
+            .map(CommandInfo::from)
+            .collect::<Vec<CommandInfo>>();
+        commands.sort_by(|a, b| (&a.group, a.name).cmp(&(&b.group, b.name)));
+        Ok(CommandOutput::Table {
+            headers: vec!["command".into(), "description".into()],
+            rows: commands.iter()
+                .map(|c| vec![c.usage(), c.desc.clone()])
+                .collect(),
+        })
+    }
+}
+
+///Commands and nested groups of commands, `CommandHandler` finds a command by walking down
+/// the groups named at the start of the input.
+#[derive(Default)]
+pub struct CommandTree {
+    commands: HashMap<String, Command>,
+    groups: HashMap<String, CommandTree>,
+}
+
+impl CommandTree {
+    ///Adds a command under its `group`, creating the groups that don't exist yet.
+    /// 
+    /// A group can't share its name with a command next to it, as the command couldn't be reached.
+    pub fn insert(&mut self, command: Command) -> Result<()> {
+        let mut node = self;
+        for (depth, group) in command.group.iter().enumerate() {
+            if node.commands.contains_key(group) {
+                return Err(anyhow!("`{}` is a command, so it can't be a group too!", 
+                                command.group[..=depth].join(" ")));
+            }
+            node = node.groups.entry(group.clone()).or_default();
         }
+        let path = command.group.iter()
+            .chain(std::iter::once(&command.name))
+            .cloned()
+            .collect::<Vec<String>>()
+            .join(" ");
+        if node.groups.contains_key(&command.name) {
+            return Err(anyhow!("`{}` is a group, so it can't be a command too!", path));
+        }
+        if node.commands.contains_key(&command.name) {
+            return Err(anyhow!("command `{}` already exists!", path));
+        }
+        node.commands.insert(command.name.clone(), command);
+        Ok(())
+    }
+    pub fn remove(&mut self, name: &str, group: &[String]) -> Option<Command> {
+        let mut node = self;
+        for g in group {
+            node = node.groups.get_mut(g)?;
+        }
+        node.commands.remove(name)
+    }
+    ///The group at the end of `path`, the whole tree when `path` is empty.
+    pub fn group(&self, path: &[String]) -> Option<&CommandTree> {
+        let mut node = self;
+        for g in path {
+            node = node.groups.get(g)?;
+        }
+        Some(node)
+    }
+    ///Finds the command named by the start of `args`, along with how many of them name it.
+    pub fn find(&self, args: &[String]) -> Result<(&Command, usize)> {
+        let mut node = self;
+        for (depth, arg) in args.iter().enumerate() {
+            if let Some(group) = node.groups.get(arg) {
+                node = group;
+            } else if let Some(command) = node.commands.get(arg) {
+                return Ok((command, depth + 1));
+            } else {
+                return Err(anyhow!("Command not found!"));
+            }
+        }
+        Err(anyhow!("No function specified"))
+    }
+    ///Names of the groups directly below this one.
+    pub fn groups(&self) -> Vec<&str> {
+        let mut ret = self.groups.keys().map(|g| g.as_str()).collect::<Vec<&str>>();
         ret.sort();
         ret
     }
+    ///Commands in this group and all the groups below it.
+    pub fn all(&self) -> Vec<&Command> {
+        let mut ret = self.commands.values().collect::<Vec<&Command>>();
+        for group in self.groups.values() {
+            ret.extend(group.all());
+        }
+        ret
+    }
 }
 
 pub struct Command {
     pub name: String,
-    pub group: Option<String>,
+    ///Path of groups the command is nested in, e.g. `["io", "csv"]` for `io csv export`.
+    pub group: Vec<String>,
     pub desc: Option<String>,
     pub args: Vec<CommandArg>,
-    pub function: Box<dyn Fn(&mut CommandContext, Vec<String>) -> Result<()>>
+    pub function: Box<CommandFn>
 }
 
+pub type CommandFn = dyn Fn(&mut CommandContext, Vec<String>) -> Result<CommandOutput>;
+
 #[derive(Debug, Clone, Ord, PartialEq, PartialOrd, Eq)]
 pub struct CommandArg {
     pub name: String,
     pub ty: String,
+    pub style: ArgStyle,
+    ///Single letter alias, e.g. `-f`, of a flag or named argument.
+    pub short: Option<char>,
+    ///Whether the argument may be left out.
+    pub optional: bool,
+    ///The value used when the argument is left out, as written in the command's `default` attribute.
+    pub default: Option<String>,
 }
-use std::hash::{Hash, Hasher};
 
-use crate::EventsManager;
-impl Hash for Command {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.name.hash(state);
-        self.group.hash(state);
+impl CommandArg {
+    ///The argument's name as typed after `--`, with underscores turned into dashes.
+    pub fn long(&self) -> String {
+        self.name.replace('_', "-")
+    }
+    ///How the argument is written in a usage line, e.g. `<id>` or `[-f|--force]`.
+    pub fn usage(&self) -> String {
+        let short = self.short.map(|s| format!("-{}|", s)).unwrap_or_default();
+        match self.style {
+            ArgStyle::Positional if self.optional => format!("[<{}>]", self.name),
+            ArgStyle::Positional => format!("<{}>", self.name),
+            ArgStyle::Rest => format!("<{}>...", self.name),
+            ArgStyle::Flag => format!("[{}--{}]", short, self.long()),
+            ArgStyle::Named => format!("[{}--{} <{}>]", short, self.long(), self.name),
+        }
     }
 }
 
-impl PartialEq for Command {
-    fn eq(&self, other: &Self) -> bool {
-        self.name == other.name
+impl std::fmt::Display for CommandArg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let short = self.short.map(|s| format!("-{}|", s)).unwrap_or_default();
+        let default = self.default.as_ref().map(|d| format!(" = {}", d)).unwrap_or_default();
+        match self.style {
+            ArgStyle::Positional if self.optional => write!(f, "[{}: {}{}]", self.name, self.ty, default),
+            ArgStyle::Positional => write!(f, "{}: {}", self.name, self.ty),
+            ArgStyle::Rest => write!(f, "{}: {}...", self.name, self.ty),
+            ArgStyle::Flag => write!(f, "[{}--{}]", short, self.long()),
+            ArgStyle::Named => write!(f, "[{}--{} <{}>{}]", short, self.long(), self.ty, default),
+        }
     }
 }
 
-impl Eq for Command {}
+///How an argument is passed to a command, set with the `flag` and `option` attributes on its parameter.
+#[derive(Debug, Clone, Copy, Ord, PartialEq, PartialOrd, Eq)]
+pub enum ArgStyle {
+    ///Given by its position among the other positional arguments.
+    Positional,
+    ///Takes all the remaining positional arguments, `ty` is the type of each of them.
+    Rest,
+    ///`--name` on its own, `true` when present.
+    Flag,
+    ///`--name value` or `--name=value`, anywhere in the input.
+    Named,
+}
+
+///The arguments of a single call, split into positional ones and the values of flags and named ones.
+#[derive(Debug, Clone, Default)]
+pub struct ArgMatches {
+    positional: Vec<String>,
+    named: HashMap<String, String>,
+}
+
+impl ArgMatches {
+    ///Sorts `args` out according to the command's `spec`, everything after a lone `--` is positional.
+    pub fn new(args: Vec<String>, spec: &[CommandArg]) -> Result<Self> {
+        let mut ret = Self::default();
+        let find = |pred: &dyn Fn(&CommandArg) -> bool| spec.iter()
+            .find(|a| matches!(a.style, ArgStyle::Flag | ArgStyle::Named) && pred(a));
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                ret.positional.extend(args.by_ref());
+            } else if let Some(long) = arg.strip_prefix("--") {
+                let (long, value) = match long.split_once('=') {
+                    Some((l, v)) => (l, Some(v.to_owned())),
+                    None => (long, None),
+                };
+                let found = find(&|a| a.long() == long)
+                    .ok_or_else(|| anyhow!("unknown argument `--{}`", long))?;
+                ret.insert(found, value, &mut args)?;
+            } else if Self::is_short(&arg) {
+                let letters = arg[1..].chars().collect::<Vec<char>>();
+                for (i, letter) in letters.iter().enumerate() {
+                    let found = find(&|a| a.short == Some(*letter))
+                        .ok_or_else(|| anyhow!("unknown argument `-{}`", letter))?;
+                    if found.style == ArgStyle::Named && i + 1 != letters.len() {
+                        return Err(anyhow!("`-{}` takes a value, so it has to come last in `{}`", letter, arg));
+                    }
+                    ret.insert(found, None, &mut args)?;
+                }
+            } else {
+                ret.positional.push(arg);
+            }
+        }
+        Ok(ret)
+    }
+    ///Whether `arg` looks like `-f` or `-fl` rather than e.g. a negative number.
+    fn is_short(arg: &str) -> bool {
+        arg.len() > 1 && arg.starts_with('-') && arg[1..].chars().all(|c| c.is_ascii_alphabetic())
+    }
+    fn insert(&mut self, arg: &CommandArg, value: Option<String>, rest: &mut impl Iterator<Item = String>) -> Result<()> {
+        let value = match (arg.style, value) {
+            (ArgStyle::Flag, None) => "true".to_owned(),
+            (_, Some(v)) => v,
+            (_, None) => rest.next()
+                .ok_or_else(|| anyhow!("`--{}` needs a value", arg.long()))?,
+        };
+        self.named.insert(arg.name.clone(), value);
+        Ok(())
+    }
+    pub fn positional(&self) -> &[String] {
+        &self.positional
+    }
+    ///The value given to a flag or named argument, `None` if it wasn't given.
+    pub fn named(&self, name: &str) -> Option<&str> {
+        self.named.get(name).map(|v| v.as_str())
+    }
+}
+use crate::{EventsManager, WeekEvent};
 
 pub struct CommandContext<'a> {
     manager: &'a mut EventsManager,
 }
 impl<'a> CommandContext<'a> {
     pub fn manager(&mut self) -> &mut EventsManager {
-        &mut self.manager
+        self.manager
+    }
+}
+
+///The value a command returns to `CommandHandler`, which hands it to a `Renderer`.
+#[derive(Debug, Clone)]
+pub enum CommandOutput {
+    None,
+    Message(String),
+    Event(Box<WeekEvent>),
+    Events(Vec<WeekEvent>),
+    ///Events to be laid out as a weekly timetable.
+    Week(Vec<WeekEvent>),
+    Table {
+        headers: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+    Many(Vec<CommandOutput>),
+    ///Nothing was done yet, the front end should ask `prompt` and run `command` if the user agrees.
+    Confirm {
+        prompt: String,
+        command: String,
+    },
+}
+
+impl CommandOutput {
+    pub fn message(message: impl Into<String>) -> Self {
+        Self::Message(message.into())
     }
 }
 
@@ -198,10 +424,62 @@ pub trait CommandModule {
 pub struct CommandInfo<'a> {
     pub name: &'a str,
     pub desc: String,
-    pub group: Option<String>,
+    pub group: Vec<String>,
     pub args: Vec<CommandArg>,
 }
 
+impl<'a> From<&'a Command> for CommandInfo<'a> {
+    fn from(command: &'a Command) -> Self {
+        Self {
+            name: command.name.as_str(),
+            desc: command.desc.clone().unwrap_or_default(),
+            group: command.group.clone(),
+            args: command.args.clone(),
+        }
+    }
+}
+
+impl CommandInfo<'_> {
+    ///The groups and name that call the command, e.g. `io csv export`.
+    pub fn path(&self) -> String {
+        self.group.iter()
+            .map(|g| g.as_str())
+            .chain(std::iter::once(self.name))
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+    ///The command's path followed by its arguments, e.g. `change <id> [-f|--force]`.
+    pub fn usage(&self) -> String {
+        std::iter::once(self.path())
+            .chain(self.args.iter().map(|a| a.usage()))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+    ///The usage line and description, followed by a table of the arguments if there are any.
+    pub fn help(&self) -> CommandOutput {
+        let mut text = format!("Usage: {}", self.usage());
+        if !self.desc.is_empty() {
+            text.push_str(&format!("\n{}", self.desc));
+        }
+        if self.args.is_empty() {
+            return CommandOutput::Message(text);
+        }
+        CommandOutput::Many(vec![
+            CommandOutput::Message(text),
+            CommandOutput::Table {
+                headers: vec!["argument".into(), "type".into(), "default".into()],
+                rows: self.args.iter()
+                    .map(|a| vec![
+                        a.usage(),
+                        a.ty.clone(),
+                        a.default.clone().unwrap_or_default(),
+                    ])
+                    .collect(),
+            },
+        ])
+    }
+}
+
 pub trait ArgParse 
 where 
     Self: Sized
@@ -242,12 +520,48 @@ impl ArgParse for chrono::NaiveTime {
     }
 }
 
+impl ArgParse for chrono::NaiveDate {
+    fn arg_parse(text: &str) -> Result<Self> {
+        Ok(chrono::NaiveDate::from_str(text)?)
+    }
+}
+
+impl ArgParse for crate::Recurrence {
+    fn arg_parse(text: &str) -> Result<Self> {
+        text.parse::<crate::Recurrence>()
+    }
+}
+
+impl ArgParse for crate::EventKind {
+    fn arg_parse(text: &str) -> Result<Self> {
+        text.parse::<crate::EventKind>()
+    }
+}
+
+impl ArgParse for crate::Room {
+    fn arg_parse(text: &str) -> Result<Self> {
+        text.parse::<crate::Room>()
+    }
+}
+
+impl ArgParse for chrono_tz::Tz {
+    fn arg_parse(text: &str) -> Result<Self> {
+        crate::timezone::parse(text)
+    }
+}
+
 impl ArgParse for u32 {
     fn arg_parse(text: &str) -> Result<Self> {
         Ok(text.parse::<u32>()?)
     }
 }
 
+impl ArgParse for f64 {
+    fn arg_parse(text: &str) -> Result<Self> {
+        Ok(text.parse::<f64>()?)
+    }
+}
+
 impl<T> ArgParse for Option<T> 
 where T: ArgParse
 {
@@ -0,0 +1,129 @@
+use std::fmt::Display;
+use chrono::{NaiveTime, Weekday};
+use super::{WeekEvent, Room};
+
+///Whether `EventsManager` may store an event that overlaps another one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    ///Refuse to store the event and return a `ScheduleConflict`.
+    #[default]
+    Reject,
+    ///Store the event anyway.
+    Allow,
+}
+
+impl From<bool> for ConflictPolicy {
+    ///`true` means the conflict check is overriden.
+    fn from(force: bool) -> Self {
+        if force { Self::Allow } else { Self::Reject }
+    }
+}
+
+///Returned when an event would overlap events that are already in the timetable.
+#[derive(Debug, Clone)]
+pub struct ScheduleConflict {
+    pub name: String,
+    pub day: Weekday,
+    pub starth: NaiveTime,
+    pub endh: NaiveTime,
+    ///The events that clash with the new one.
+    pub conflicts: Vec<WeekEvent>,
+}
+
+impl Display for ScheduleConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` ({} {}-{}) overlaps with:",
+            self.name, self.day, self.starth.format("%H:%M"), self.endh.format("%H:%M"))?;
+        for c in &self.conflicts {
+            write!(f, "\n  {}", summary(c))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ScheduleConflict {}
+
+///Returned when an event would take place in a room that's already taken at that time,
+/// which no `ConflictPolicy` allows.
+#[derive(Debug, Clone)]
+pub struct RoomConflict {
+    pub room: Room,
+    pub name: String,
+    ///The events that already take place in the room.
+    pub conflicts: Vec<WeekEvent>,
+}
+
+impl Display for RoomConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` can't take place in {}, it's taken by:", self.name, self.room)?;
+        for c in &self.conflicts {
+            write!(f, "\n  {}", summary(c))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RoomConflict {}
+
+///Whether two time ranges on the same day overlap, ranges that only touch don't.
+pub fn overlaps(a: (NaiveTime, NaiveTime), b: (NaiveTime, NaiveTime)) -> bool {
+    a.0 < b.1 && b.0 < a.1
+}
+
+///Whether two events can take place at the same time.
+pub fn clash(a: &WeekEvent, b: &WeekEvent) -> bool {
+    a.day == b.day
+        && (a.term_id == b.term_id || a.term_id.is_none() || b.term_id.is_none())
+        && overlaps((a.starth, a.endh), (b.starth, b.endh))
+        && match (a.date, b.date) {
+            (Some(x), Some(y)) => x == y,
+            (Some(x), None) => b.occurs_on(x),
+            (None, Some(y)) => a.occurs_on(y),
+            (None, None) => a.recurrence.may_coincide(&b.recurrence),
+        }
+}
+
+///Events from `event_s` that clash with `candidate`, not counting `candidate` itself.
+pub fn clashing(event_s: &[WeekEvent], candidate: &WeekEvent) -> Vec<WeekEvent> {
+    event_s.iter()
+        .filter(|e| candidate.id.is_none() || e.id != candidate.id)
+        .filter(|e| clash(e, candidate))
+        .cloned()
+        .collect()
+}
+
+///Events from `event_s` that take place in the same room as `candidate` at the same time.
+pub fn room_clashing(event_s: &[WeekEvent], candidate: &WeekEvent) -> Vec<WeekEvent> {
+    let Some(room) = &candidate.room else {
+        return vec![];
+    };
+    clashing(event_s, candidate)
+        .into_iter()
+        .filter(|e| e.room.as_ref().map(|r| r.same_as(room)).unwrap_or(false))
+        .collect()
+}
+
+///Finds every pair of overlapping events.
+pub fn find_all(event_s: &[WeekEvent]) -> Vec<(WeekEvent, WeekEvent)> {
+    let mut sorted = event_s.to_vec();
+    sorted.sort_by_key(|e| (e.day.num_days_from_monday(), e.starth, e.id));
+    let mut ret = vec![];
+    for (i, a) in sorted.iter().enumerate() {
+        for b in sorted.iter().skip(i + 1) {
+            if clash(a, b) {
+                ret.push((a.clone(), b.clone()));
+            }
+        }
+    }
+    ret
+}
+
+///Short one-line description of an event.
+pub fn summary(event: &WeekEvent) -> String {
+    format!("#{} {} ({} {}-{})",
+        event.id.map(|i| i.to_string()).unwrap_or_else(|| "?".to_owned()),
+        event.name,
+        event.day,
+        event.starth.format("%H:%M"),
+        event.endh.format("%H:%M"))
+}
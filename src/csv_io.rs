@@ -0,0 +1,194 @@
+use std::fmt::Display;
+use std::io;
+use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
+use anyhow::Result;
+use super::{WeekEvent, NewWeekEvent, Recurrence, EventKind, Room};
+
+///Columns written by `export`, `import` accepts them in any order and ignores `id`.
+/// 
+/// `recurrence` may be left out on import, in which case events repeat weekly, and so may
+/// `date`, which is only filled in for one-off events, and `room`. Files from before event kinds
+/// may have a boolean `is_lecture` column instead of `kind`.
+pub const HEADER: [&str; 9] = ["id", "name", "day", "starth", "endh", "kind", "recurrence", "date", "room"];
+const REQUIRED: [&str; 5] = ["name", "day", "starth", "endh", "kind"];
+const LEGACY_KIND: &str = "is_lecture";
+
+///Writes events as CSV, with a header row.
+pub fn export<W: io::Write>(event_s: &[WeekEvent], writer: W) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record(HEADER)?;
+    for e in event_s {
+        writer.write_record([
+            e.id.map(|i| i.to_string()).unwrap_or_default(),
+            e.name.clone(),
+            e.day.to_string(),
+            e.starth.to_string(),
+            e.endh.to_string(),
+            e.kind.to_string(),
+            e.recurrence.to_string(),
+            e.date.map(|d| d.to_string()).unwrap_or_default(),
+            e.room.as_ref().map(|r| r.to_string()).unwrap_or_default(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+///Reads events from CSV, the first row must be a header naming the columns.
+///
+/// Every row is checked before returning, so a single `CsvImportError` reports
+/// all rows that failed to parse.
+pub fn import<R: io::Read>(reader: R) -> Result<Vec<NewWeekEvent>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    let header = reader.headers()?.clone();
+    let column = |name: &str| header.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let legacy = column("kind").is_none() && column(LEGACY_KIND).is_some();
+    let missing = REQUIRED.iter()
+        .filter(|c| column(c).is_none() && !(legacy && **c == "kind"))
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(anyhow::anyhow!("CSV header is missing column(s): {}",
+            missing.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(", ")));
+    }
+    let [name, day, starth, endh] = ["name", "day", "starth", "endh"].map(|c| column(c).unwrap());
+    let (kind_column, kind) = if legacy {
+        (LEGACY_KIND, column(LEGACY_KIND).unwrap())
+    } else {
+        ("kind", column("kind").unwrap())
+    };
+    let recurrence = column("recurrence");
+    let date = column("date");
+    let room = column("room");
+
+    let mut ret = vec![];
+    let mut errors = vec![];
+    for record in reader.records() {
+        let record = record?;
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+        let field = |index: usize| record.get(index).unwrap_or_default();
+        let mut error = |column: &str, message: String| errors.push(CsvRowError {
+            line,
+            column: column.to_owned(),
+            message,
+        });
+
+        let parsed_name = field(name).to_owned();
+        if parsed_name.is_empty() {
+            error("name", "name is empty".to_owned());
+        }
+        let parsed_day = field(day).parse::<Weekday>()
+            .map_err(|_| error("day", format!("`{}` is not a weekday", field(day))))
+            .ok();
+        let mut time = |column: &str, index: usize| field(index).parse::<NaiveTime>()
+            .map_err(|_| error(column, format!("`{}` is not a time", field(index))))
+            .ok();
+        let parsed_starth = time("starth", starth);
+        let parsed_endh = time("endh", endh);
+        let parsed_kind = if legacy {
+            parse_bool(field(kind))
+                .map(EventKind::from)
+                .ok_or_else(|| error(kind_column, format!("`{}` is not a boolean", field(kind))))
+                .ok()
+        } else if field(kind).is_empty() {
+            Some(EventKind::Other)
+        } else {
+            field(kind).parse::<EventKind>()
+                .map_err(|e| error(kind_column, e.to_string()))
+                .ok()
+        };
+        let parsed_recurrence = match recurrence.map(field).filter(|r| !r.is_empty()) {
+            Some(r) => r.parse::<Recurrence>()
+                .map_err(|e| error("recurrence", e.to_string()))
+                .ok(),
+            None => Some(Recurrence::Weekly),
+        };
+        let parsed_date = match date.map(field).filter(|d| !d.is_empty()) {
+            Some(d) => d.parse::<NaiveDate>()
+                .map_err(|_| error("date", format!("`{}` is not a date", d)))
+                .ok()
+                .map(Some),
+            None => Some(None),
+        };
+        let parsed_room = match room.map(field).filter(|r| !r.is_empty()) {
+            Some(r) => r.parse::<Room>()
+                .map_err(|e| error("room", e.to_string()))
+                .ok()
+                .map(Some),
+            None => Some(None),
+        };
+        if let (Some(d), Some(Some(date))) = (parsed_day, parsed_date) {
+            if date.weekday() != d {
+                error("date", format!("`{}` is not a {}", date, d));
+                continue;
+            }
+        }
+
+        if let (Some(s), Some(e)) = (parsed_starth, parsed_endh) {
+            if e <= s {
+                error("endh", format!("`{}` is not after the start at `{}`", e, s));
+                continue;
+            }
+        }
+        if let (Some(d), Some(s), Some(e), Some(k), Some(r), Some(date), Some(room)) =
+            (parsed_day, parsed_starth, parsed_endh, parsed_kind, parsed_recurrence, parsed_date, parsed_room) {
+            ret.push(NewWeekEvent {
+                name: parsed_name,
+                day: d,
+                starth: s,
+                endh: e,
+                kind: k,
+                recurrence: r,
+                term_id: None,
+                date,
+                room,
+                course: None,
+            });
+        }
+    }
+    if !errors.is_empty() {
+        return Err(CsvImportError { rows: errors }.into());
+    }
+    Ok(ret)
+}
+
+fn parse_bool(text: &str) -> Option<bool> {
+    match text.to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "y" => Some(true),
+        "false" | "0" | "no" | "n" | "" => Some(false),
+        _ => None,
+    }
+}
+
+///A single field that failed to parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvRowError {
+    pub line: u64,
+    pub column: String,
+    pub message: String,
+}
+
+impl Display for CsvRowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column `{}`: {}", self.line, self.column, self.message)
+    }
+}
+
+///Returned by `import` when one or more rows are invalid.
+#[derive(Debug, Clone)]
+pub struct CsvImportError {
+    pub rows: Vec<CsvRowError>,
+}
+
+impl Display for CsvImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} invalid field(s) in CSV input", self.rows.len())?;
+        for row in &self.rows {
+            write!(f, "\n  {}", row)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CsvImportError {}
@@ -0,0 +1,150 @@
+use chrono::{NaiveTime, Timelike, Weekday};
+use super::{WeekEvent, EventKind};
+
+const LANE_WIDTH: usize = 14;
+const DAYS: [Weekday; 7] = [
+    Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu,
+    Weekday::Fri, Weekday::Sat, Weekday::Sun,
+];
+
+///Renders events as a Monday to Sunday timetable with one row per hour.
+///
+/// Events are prefixed with their kind's marker, e.g. `*` for lectures. An event's first row 
+/// holds its name and the second its hours, events that overlap are placed side by side within their day.
+pub fn render(event_s: &[WeekEvent]) -> String {
+    render_with(event_s, false)
+}
+
+///Same as `render`, with every event's cells painted in its course's or kind's color when `colored` is set.
+pub fn render_with(event_s: &[WeekEvent], colored: bool) -> String {
+    if event_s.is_empty() {
+        return "No events found!".to_owned();
+    }
+    let first_hour = event_s.iter()
+        .map(|e| e.starth.hour())
+        .min()
+        .unwrap_or_default();
+    let last_hour = event_s.iter()
+        .map(end_hour)
+        .max()
+        .unwrap_or_default();
+
+    let columns = DAYS.iter()
+        .map(|d| lanes(event_s.iter().filter(|e| e.day == *d).collect()))
+        .collect::<Vec<Vec<Vec<&WeekEvent>>>>();
+    let widths = columns.iter()
+        .map(|c| c.len().max(1) * LANE_WIDTH - 1)
+        .collect::<Vec<usize>>();
+
+    let mut lines = vec![];
+    let mut header = "      ".to_owned();
+    let mut separator = "------".to_owned();
+    for (d, w) in DAYS.iter().zip(widths.iter()) {
+        header.push_str(&format!("| {:<width$} ", d.to_string(), width = w));
+        separator.push_str(&format!("+{}", "-".repeat(w + 2)));
+    }
+    lines.push(header.trim_end().to_owned());
+    lines.push(separator);
+
+    for hour in first_hour..last_hour {
+        let slot_start = NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+        let mut line = format!("{:02}:00 ", hour);
+        for (lanes, w) in columns.iter().zip(widths.iter()) {
+            let cells = if lanes.is_empty() {
+                vec![None]
+            } else {
+                lanes.iter()
+                    .map(|lane| lane.iter().find(|e| occupies(e, hour)))
+                    .collect()
+            };
+            let cells = cells.iter()
+                .map(|e| {
+                    let text = e.map(|e| cell(e, slot_start)).unwrap_or_default();
+                    let text = format!("{:<width$}", text, width = LANE_WIDTH - 1);
+                    match e {
+                        Some(e) if colored => e.paint(&text),
+                        _ => text,
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join(" ");
+            // padding has to ignore the invisible color codes
+            let visible = cells.chars().count() - if colored { escapes(&cells) } else { 0 };
+            line.push_str(&format!("| {}{} ", cells, " ".repeat(w.saturating_sub(visible))));
+        }
+        lines.push(line.trim_end().to_owned());
+    }
+    lines.push(String::new());
+    lines.push(EventKind::ALL.iter()
+        .filter(|k| !k.marker().is_empty())
+        .map(|k| format!("{} {}", k.marker(), k))
+        .collect::<Vec<String>>()
+        .join("  "));
+    lines.join("\n")
+}
+
+///Number of characters taken up by ANSI escape sequences in `text`.
+fn escapes(text: &str) -> usize {
+    let mut ret = 0;
+    let mut in_escape = false;
+    for c in text.chars() {
+        if c == '\x1b' {
+            in_escape = true;
+        }
+        if in_escape {
+            ret += 1;
+            if c == 'm' {
+                in_escape = false;
+            }
+        }
+    }
+    ret
+}
+
+///Splits the events of a single day into lanes in which no two events share an hour row.
+/// 
+/// Events that merely follow each other within the same hour still get separate lanes, 
+/// since a row can only show one event per lane.
+fn lanes(mut event_s: Vec<&WeekEvent>) -> Vec<Vec<&WeekEvent>> {
+    event_s.sort_by_key(|e| (e.starth, e.endh));
+    let mut lanes: Vec<Vec<&WeekEvent>> = vec![];
+    for e in event_s {
+        let free = lanes.iter_mut()
+            .find(|l| l.last().map(|last| end_hour(last) <= e.starth.hour()).unwrap_or(true));
+        match free {
+            Some(lane) => lane.push(e),
+            None => lanes.push(vec![e]),
+        }
+    }
+    lanes
+}
+
+///Hour at which the last row occupied by an event ends.
+fn end_hour(event: &WeekEvent) -> u32 {
+    if event.endh <= event.starth {
+        return event.starth.hour() + 1;
+    }
+    let hour = event.endh.hour();
+    if event.endh.minute() > 0 || event.endh.second() > 0 {
+        hour + 1
+    } else {
+        hour
+    }
+}
+
+fn occupies(event: &WeekEvent, hour: u32) -> bool {
+    event.starth.hour() <= hour && hour < end_hour(event)
+}
+
+fn cell(event: &WeekEvent, slot_start: NaiveTime) -> String {
+    let first_row = event.starth.hour() == slot_start.hour();
+    let second_row = event.starth.hour() + 1 == slot_start.hour();
+    let text = if first_row {
+        format!("{}{}", event.kind.marker(), event.name)
+    } else if second_row {
+        format!("{}-{}", event.starth.format("%H:%M"), event.endh.format("%H:%M"))
+    } else {
+        "~".to_owned()
+    };
+    text.chars().take(LANE_WIDTH - 1).collect()
+}
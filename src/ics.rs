@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+use chrono::{
+    Datelike,
+    NaiveDate,
+    NaiveDateTime,
+    TimeZone,
+    Utc,
+    Weekday,
+};
+use anyhow::{Result, anyhow};
+use super::{WeekEvent, NewWeekEvent, Recurrence, EventKind, Room};
+
+const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+const MAX_LINE_LENGTH: usize = 75;
+
+///Renders events as an iCalendar (RFC 5545) document.
+///
+/// Every event becomes a `VEVENT` that repeats weekly, or every few weeks depending on its 
+/// `Recurrence`, starting on its first occurence on or after `semester_start`. One-off events
+/// become single `VEVENT`s on their date. Times are given in `tz`, named by its `TZID`.
+pub fn export(event_s: &[WeekEvent], semester_start: NaiveDate, tz: chrono_tz::Tz) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        "PRODID:-//syd//timetable//EN".to_owned(),
+    ];
+    let stamp = Utc::now().naive_utc().format(DATE_TIME_FORMAT);
+    for event in event_s {
+        let date = first_occurence(event, semester_start);
+        lines.push("BEGIN:VEVENT".to_owned());
+        lines.push(format!("UID:{}", uid(event)));
+        lines.push(format!("DTSTAMP:{}Z", stamp));
+        lines.push(format!("DTSTART;TZID={}:{}", tz.name(), date.and_time(event.starth).format(DATE_TIME_FORMAT)));
+        lines.push(format!("DTEND;TZID={}:{}", tz.name(), date.and_time(event.endh).format(DATE_TIME_FORMAT)));
+        if event.date.is_none() {
+            let interval = match event.recurrence {
+                Recurrence::Weekly => String::new(),
+                Recurrence::Odd | Recurrence::Even => ";INTERVAL=2".to_owned(),
+                Recurrence::Every { weeks, .. } => format!(";INTERVAL={}", weeks),
+            };
+            lines.push(format!("RRULE:FREQ=WEEKLY{};BYDAY={}", interval, weekday_code(event.day)));
+        }
+        lines.push(format!("SUMMARY:{}", escape(&event.name)));
+        if let Some(room) = &event.room {
+            lines.push(format!("LOCATION:{}", escape(&room.to_string())));
+        }
+        if event.kind != EventKind::Other {
+            lines.push(format!("CATEGORIES:{}", event.kind.to_string().to_ascii_uppercase()));
+        }
+        lines.push("END:VEVENT".to_owned());
+    }
+    lines.push("END:VCALENDAR".to_owned());
+
+    let mut ret = String::new();
+    for line in lines {
+        ret.push_str(&fold(&line));
+        ret.push_str("\r\n");
+    }
+    ret
+}
+
+///Returns the date of the first occurence of an event on or after `semester_start`, or the
+/// date of a one-off event.
+pub fn first_occurence(event: &WeekEvent, semester_start: NaiveDate) -> NaiveDate {
+    if let Some(date) = event.date {
+        return date;
+    }
+    let offset = (7 + event.day.num_days_from_monday()
+        - semester_start.weekday().num_days_from_monday()) % 7;
+    let mut date = semester_start + chrono::Duration::days(offset as i64);
+    // every recurrence repeats within a year, give up if it somehow doesn't
+    for _ in 0..53 {
+        if event.recurrence.occurs_on(date) {
+            break;
+        }
+        date += chrono::Duration::weeks(1);
+    }
+    date
+}
+
+fn uid(event: &WeekEvent) -> String {
+    match event.id {
+        Some(i) => format!("event-{}@syd", i),
+        None => format!("event-{}-{}-{}@syd",
+            weekday_code(event.day),
+            event.starth.format("%H%M"),
+            event.name.replace(char::is_whitespace, "-")),
+    }
+}
+
+///Result of parsing an iCalendar document.
+#[derive(Debug, Clone, Default)]
+pub struct IcsImport {
+    ///Events that can be stored in the timetable.
+    pub events: Vec<NewWeekEvent>,
+    ///Events that were left out, along with the reason why.
+    pub skipped: Vec<SkippedEvent>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SkippedEvent {
+    pub summary: String,
+    pub reason: String,
+}
+
+///Parses an iCalendar document, mapping weekly recurring `VEVENT`s onto `NewWeekEvent`s.
+///
+/// `VEVENT`s without an `RRULE` become one-off events. A rule with several days in `BYDAY` is expanded into one event per day.
+/// Events that can't be represented in the timetable, like series that end (`UNTIL`, `COUNT`) or skip
+/// dates (`EXDATE`), are reported in `IcsImport::skipped` instead of failing the whole import, only a 
+/// malformed document returns `Err`. UTC times and times with a `TZID` are converted to `tz`, times
+/// without either are taken as they are.
+pub fn import(text: &str, tz: chrono_tz::Tz) -> Result<IcsImport> {
+    let mut ret = IcsImport::default();
+    let mut current: Option<HashMap<String, Property>> = None;
+    for (n, line) in unfold(text).iter().enumerate() {
+        let property = Property::parse(line)
+            .ok_or_else(|| anyhow!("malformed content line {}: {}", n + 1, line))?;
+        match (property.name.as_str(), property.value.as_str()) {
+            ("BEGIN", "VEVENT") => {
+                if current.is_some() {
+                    return Err(anyhow!("nested VEVENT at line {}", n + 1));
+                }
+                current = Some(HashMap::new());
+            },
+            ("END", "VEVENT") => {
+                let props = current.take()
+                    .ok_or_else(|| anyhow!("unexpected END:VEVENT at line {}", n + 1))?;
+                let summary = props.get("SUMMARY")
+                    .map(|p| unescape(&p.value))
+                    .unwrap_or_default();
+                match map_event(&props, &summary, tz) {
+                    Ok(mut e) => ret.events.append(&mut e),
+                    Err(e) => ret.skipped.push(SkippedEvent { 
+                        summary, 
+                        reason: e.to_string() 
+                    }),
+                }
+            },
+            _ => {
+                if let Some(props) = current.as_mut() {
+                    props.entry(property.name.clone()).or_insert(property);
+                }
+            }
+        }
+    }
+    if current.is_some() {
+        return Err(anyhow!("unterminated VEVENT"));
+    }
+    Ok(ret)
+}
+
+#[derive(Debug, Clone)]
+struct Property {
+    name: String,
+    params: HashMap<String, String>,
+    value: String,
+}
+
+impl Property {
+    fn parse(line: &str) -> Option<Self> {
+        let (head, value) = split_unquoted(line, ':')?;
+        let mut head = head.split(';');
+        let name = head.next()?.to_ascii_uppercase();
+        let params = head.filter_map(|p| {
+                let (k, v) = p.split_once('=')?;
+                Some((k.to_ascii_uppercase(), v.trim_matches('"').to_owned()))
+            })
+            .collect();
+        Some(Self { name, params, value: value.to_owned() })
+    }
+}
+
+fn split_unquoted(line: &str, delim: char) -> Option<(&str, &str)> {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if c == delim && !quoted {
+            return Some((&line[..i], &line[i + 1..]));
+        }
+    }
+    None
+}
+
+fn map_event(props: &HashMap<String, Property>, summary: &str, tz: chrono_tz::Tz) -> Result<Vec<NewWeekEvent>> {
+    if summary.is_empty() {
+        return Err(anyhow!("event has no SUMMARY"));
+    }
+    let start = props.get("DTSTART")
+        .ok_or_else(|| anyhow!("event has no DTSTART"))?;
+    let start = parse_date_time(start, tz)?;
+    let end = match (props.get("DTEND"), props.get("DURATION")) {
+        (Some(end), _) => parse_date_time(end, tz)?,
+        (None, Some(duration)) => start + parse_duration(&duration.value)?,
+        (None, None) => return Err(anyhow!("event has neither DTEND nor DURATION")),
+    };
+    if end.date() != start.date() {
+        return Err(anyhow!("events spanning several days are not supported"));
+    }
+    let kind = props.get("CATEGORIES")
+        .and_then(|c| c.value.split(',').find_map(|c| c.trim().parse::<EventKind>().ok()))
+        .unwrap_or_default();
+    // locations that don't look like `<building> <room number>` are left out
+    let room = props.get("LOCATION")
+        .and_then(|l| unescape(&l.value).parse::<Room>().ok());
+    let Some(rule) = props.get("RRULE") else {
+        return Ok(vec![NewWeekEvent {
+            name: summary.to_owned(),
+            day: start.weekday(),
+            starth: start.time(),
+            endh: end.time(),
+            kind,
+            recurrence: Recurrence::Weekly,
+            term_id: None,
+            date: Some(start.date()),
+            room,
+            course: None,
+        }]);
+    };
+    if props.contains_key("EXDATE") {
+        return Err(anyhow!("recurrences with excluded dates (EXDATE) are not supported"));
+    }
+    if props.contains_key("RDATE") {
+        return Err(anyhow!("recurrences with additional dates (RDATE) are not supported"));
+    }
+    let (days, interval) = weekly_days(&rule.value, start.weekday())?;
+    let recurrence = match interval {
+        1 => Recurrence::Weekly,
+        weeks => Recurrence::Every { weeks, anchor: start.date() },
+    };
+
+    Ok(days.into_iter()
+        .map(|d| NewWeekEvent {
+            name: summary.to_owned(),
+            day: d,
+            starth: start.time(),
+            endh: end.time(),
+            kind,
+            recurrence,
+            term_id: None,
+            date: None,
+            room: room.clone(),
+            course: None,
+        })
+        .collect())
+}
+
+///Returns the weekdays on which a weekly `RRULE` repeats and its interval in weeks.
+fn weekly_days(rule: &str, start_day: Weekday) -> Result<(Vec<Weekday>, u32)> {
+    let mut days = vec![];
+    let mut interval = 1;
+    for part in rule.split(';') {
+        let (key, value) = part.split_once('=')
+            .ok_or_else(|| anyhow!("malformed RRULE part `{}`", part))?;
+        match key.to_ascii_uppercase().as_str() {
+            "FREQ" if value.eq_ignore_ascii_case("WEEKLY") => {},
+            "FREQ" => return Err(anyhow!("unsupported recurrence frequency {}", value)),
+            "INTERVAL" => {
+                interval = value.parse::<u32>()
+                    .ok()
+                    .filter(|i| *i > 0)
+                    .ok_or_else(|| anyhow!("invalid recurrence interval {}", value))?;
+            },
+            "BYDAY" => {
+                for code in value.split(',') {
+                    days.push(parse_weekday_code(code)
+                        .ok_or_else(|| anyhow!("unsupported BYDAY value {}", code))?);
+                }
+            },
+            "UNTIL" => return Err(anyhow!("recurrences ending on a date (UNTIL) are not supported")),
+            "COUNT" => return Err(anyhow!("recurrences with a number of occurences (COUNT) are not supported")),
+            "WKST" => {},
+            _ => return Err(anyhow!("unsupported recurrence rule part {}", key)),
+        }
+    }
+    if !rule.to_ascii_uppercase().contains("FREQ=") {
+        return Err(anyhow!("RRULE has no FREQ"));
+    }
+    if days.is_empty() {
+        days.push(start_day);
+    }
+    days.sort_by_key(|d| d.num_days_from_monday());
+    days.dedup();
+    Ok((days, interval))
+}
+
+fn parse_date_time(property: &Property, tz: chrono_tz::Tz) -> Result<NaiveDateTime> {
+    if property.params.get("VALUE").map(|v| v.as_str()) == Some("DATE") {
+        return Err(anyhow!("all-day events are not supported"));
+    }
+    let value = property.value.trim();
+    if let Some(utc) = value.strip_suffix('Z') {
+        let utc = NaiveDateTime::parse_from_str(utc, DATE_TIME_FORMAT)?;
+        return Ok(tz.from_utc_datetime(&utc).naive_local());
+    }
+    let local = NaiveDateTime::parse_from_str(value, DATE_TIME_FORMAT)
+        .map_err(|_| anyhow!("unsupported date-time `{}`", value))?;
+    let Some(zone) = property.params.get("TZID") else {
+        return Ok(local);
+    };
+    let zone = zone.parse::<chrono_tz::Tz>()
+        .map_err(|_| anyhow!("unknown time zone `{}`", zone))?;
+    let at = zone.from_local_datetime(&local)
+        .earliest()
+        .ok_or_else(|| anyhow!("{} doesn't exist in {}", local, zone))?;
+    Ok(at.with_timezone(&tz).naive_local())
+}
+
+///Parses the time part of an RFC 5545 duration, e.g. `PT1H30M`.
+fn parse_duration(text: &str) -> Result<chrono::Duration> {
+    let invalid = || anyhow!("unsupported duration `{}`", text);
+    let time = text.strip_prefix("PT").ok_or_else(invalid)?;
+    let mut ret = chrono::Duration::zero();
+    let mut number = String::new();
+    for c in time.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let n = number.parse::<i64>().map_err(|_| invalid())?;
+        number.clear();
+        ret += match c {
+            'H' => chrono::Duration::hours(n),
+            'M' => chrono::Duration::minutes(n),
+            'S' => chrono::Duration::seconds(n),
+            _ => return Err(invalid()),
+        };
+    }
+    if !number.is_empty() {
+        return Err(invalid());
+    }
+    Ok(ret)
+}
+
+fn parse_weekday_code(code: &str) -> Option<Weekday> {
+    use chrono::Weekday::*;
+    [Mon, Tue, Wed, Thu, Fri, Sat, Sun].into_iter()
+        .find(|d| weekday_code(*d).eq_ignore_ascii_case(code.trim()))
+}
+
+///Joins folded content lines back together.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in text.lines() {
+        if let Some(rest) = line.strip_prefix([' ', '\t']) {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(rest);
+                continue;
+            }
+        }
+        if !line.is_empty() {
+            lines.push(line.to_owned());
+        }
+    }
+    lines
+}
+
+fn unescape(text: &str) -> String {
+    let mut ret = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            ret.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => ret.push('\n'),
+            Some(c) => ret.push(c),
+            None => ret.push('\\'),
+        }
+    }
+    ret
+}
+
+pub(crate) fn weekday_code(day: chrono::Weekday) -> &'static str {
+    use chrono::Weekday::*;
+    match day {
+        Mon => "MO",
+        Tue => "TU",
+        Wed => "WE",
+        Thu => "TH",
+        Fri => "FR",
+        Sat => "SA",
+        Sun => "SU",
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut ret = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => ret.push_str("\\\\"),
+            ';' => ret.push_str("\\;"),
+            ',' => ret.push_str("\\,"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => {},
+            _ => ret.push(c),
+        }
+    }
+    ret
+}
+
+///Splits a content line into chunks of at most 75 octets, as required by RFC 5545.
+fn fold(line: &str) -> String {
+    let mut ret = String::with_capacity(line.len());
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > MAX_LINE_LENGTH {
+            ret.push_str("\r\n ");
+            len = 1;
+        }
+        len += c.len_utf8();
+        ret.push(c);
+    }
+    ret
+}
@@ -0,0 +1,92 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Serializer};
+
+///What sort of class an event is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum EventKind {
+    Lecture,
+    Lab,
+    Seminar,
+    Exercise,
+    Exam,
+    #[default]
+    Other,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 6] = [
+        EventKind::Lecture, EventKind::Lab, EventKind::Seminar,
+        EventKind::Exercise, EventKind::Exam, EventKind::Other,
+    ];
+    ///Single character that marks events of this kind in the week grid.
+    pub fn marker(&self) -> &'static str {
+        match self {
+            EventKind::Lecture => "*",
+            EventKind::Lab => "+",
+            EventKind::Seminar => "#",
+            EventKind::Exercise => "=",
+            EventKind::Exam => "!",
+            EventKind::Other => "",
+        }
+    }
+    ///ANSI SGR code of the color events of this kind are printed in.
+    pub fn color(&self) -> &'static str {
+        match self {
+            EventKind::Lecture => "34",
+            EventKind::Lab => "32",
+            EventKind::Seminar => "35",
+            EventKind::Exercise => "36",
+            EventKind::Exam => "1;31",
+            EventKind::Other => "0",
+        }
+    }
+    ///Wraps `text` in this kind's color.
+    pub fn paint(&self, text: &str) -> String {
+        paint(self.color(), text)
+    }
+}
+
+///Wraps `text` in the ANSI SGR `code`, resetting it afterwards.
+pub fn paint(code: &str, text: &str) -> String {
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
+
+impl From<bool> for EventKind {
+    ///`true` is a lecture, like the old `isLecture` flag.
+    fn from(is_lecture: bool) -> Self {
+        if is_lecture { Self::Lecture } else { Self::Other }
+    }
+}
+
+impl Display for EventKind {
+    ///The format stored in the database, e.g. `lecture`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            EventKind::Lecture => "lecture",
+            EventKind::Lab => "lab",
+            EventKind::Seminar => "seminar",
+            EventKind::Exercise => "exercise",
+            EventKind::Exam => "exam",
+            EventKind::Other => "other",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+impl FromStr for EventKind {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        EventKind::ALL.into_iter()
+            .find(|k| k.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| anyhow!(
+                "`{}` is not an event kind, expected one of `lecture`, `lab`, `seminar`, `exercise`, `exam` or `other`", s))
+    }
+}
+
+impl Serialize for EventKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
@@ -1,61 +1,195 @@
 #[cfg(test)]
 mod tests;
+//lets the tests use the paths that `syd_macros` generates
+#[cfg(test)]
+extern crate self as syd;
 
 pub mod models;
 pub mod schema;
 pub mod commands;
+pub mod ics;
+pub mod csv_io;
+pub mod render;
+pub mod grid;
+pub mod conflict;
+pub mod validation;
+pub mod slots;
+pub mod recurrence;
+pub mod kind;
+pub mod timezone;
 
 use diesel::prelude::*;
 use anyhow::{
     Result, Ok,
 };
 use diesel::sqlite::SqliteConnection;
+use diesel_migrations::{
+    embed_migrations,
+    EmbeddedMigrations,
+    MigrationHarness,
+};
 use models::*;
 pub use models::transformed::*;
+pub use conflict::{ConflictPolicy, ScheduleConflict, RoomConflict};
+pub use validation::ValidationError;
+pub use recurrence::Recurrence;
+pub use kind::EventKind;
 pub use schema::events::{
     self,
     dsl::*,
 };
-use chrono;
 
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+///How many days ahead `EventsManager::upcoming` looks for events.
+const LOOKAHEAD_DAYS: usize = 366;
 
 pub struct EventsManager {
     connection: SqliteConnection,
 }
 
 impl EventsManager {
+    #[allow(clippy::should_implement_trait)]
     pub fn default() -> Result<Self> {
         use dotenvy::dotenv;
         use std::env;
         dotenv()?;
         let database_url = env::var("DATABASE_URL")?;
-        Ok(Self { connection: Self::establish_connection(&database_url)? })
+        let mut manager = Self { connection: Self::establish_connection(&database_url)? };
+        if let Some(tz) = timezone::configured()? {
+            manager.seed_timezone(tz)?;
+        }
+        Ok(manager)
     }
     pub fn custom(database_url: &str) -> Result<Self> {
         Ok(Self {
             connection: Self::establish_connection(database_url)?
         })
     }
+    ///Runs `f` inside a database transaction, which is rolled back if `f` returns `Err`.
+    fn transaction<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        use diesel::connection::{AnsiTransactionManager, TransactionManager};
+        AnsiTransactionManager::begin_transaction(&mut self.connection)?;
+        let result = f(self);
+        if result.is_ok() {
+            if let Err(e) = AnsiTransactionManager::commit_transaction(&mut self.connection) {
+                //a failed commit leaves the transaction open, which would swallow every later write
+                let _ = AnsiTransactionManager::rollback_transaction(&mut self.connection);
+                return Err(e.into());
+            }
+        } else {
+            AnsiTransactionManager::rollback_transaction(&mut self.connection)?;
+        }
+        result
+    }
     fn establish_connection(database_url: &str) -> Result<SqliteConnection> {
-        let connection = SqliteConnection::establish(database_url)?;
+        let mut connection = SqliteConnection::establish(database_url)?;
+        connection.run_pending_migrations(MIGRATIONS)
+            .map_err(|e| anyhow::anyhow!(e))?;
+        //sqlite leaves `ON DELETE` clauses unenforced unless asked to
+        diesel::sql_query("PRAGMA foreign_keys = ON")
+            .execute(&mut connection)?;
         Ok(connection)
     }
-    pub fn add_event(&mut self, new_event: NewWeekEvent) -> Result<()>{
-        let new_event: NewEvent = new_event.into();
-        diesel::insert_into(events::table)
-            .values(new_event)
+    fn setting(&mut self, setting: &str) -> Result<Option<String>> {
+        use schema::settings::dsl as s;
+        Ok(s::settings.filter(s::key.eq(setting))
+            .select(s::value)
+            .first::<String>(&mut self.connection)
+            .optional()?)
+    }
+    fn set_setting(&mut self, setting: &str, to: &str) -> Result<()> {
+        use schema::settings::dsl as s;
+        diesel::replace_into(s::settings)
+            .values((s::key.eq(setting), s::value.eq(to)))
             .execute(&mut self.connection)?;
         Ok(())
     }
+    ///The timezone stored with the timetable, or the system's local one if none was stored.
+    pub fn timezone(&mut self) -> Result<chrono_tz::Tz> {
+        match self.setting("timezone")? {
+            Some(zone) => timezone::parse(&zone),
+            None => Ok(timezone::system()),
+        }
+    }
+    ///Stores the timezone that "today" and "now" are taken in.
+    pub fn set_timezone(&mut self, tz: chrono_tz::Tz) -> Result<()> {
+        self.set_setting("timezone", tz.name())
+    }
+    ///Stores `tz` as the timetable's timezone unless one is stored already, so that a timezone
+    /// set with `set_timezone` outlives the one configured through `timezone::TIMEZONE_VAR`.
+    fn seed_timezone(&mut self, tz: chrono_tz::Tz) -> Result<()> {
+        if self.setting("timezone")?.is_none() {
+            self.set_timezone(tz)?;
+        }
+        Ok(())
+    }
+    ///The current time in the timetable's timezone.
+    pub fn now(&mut self) -> Result<chrono::DateTime<chrono_tz::Tz>> {
+        let tz = self.timezone()?;
+        Ok(chrono::Utc::now().with_timezone(&tz))
+    }
+    ///Today's date in the timetable's timezone.
+    pub fn today(&mut self) -> Result<chrono::NaiveDate> {
+        Ok(self.now()?.date_naive())
+    }
+    ///Stores a new event, unless it overlaps another one and `policy` is `ConflictPolicy::Reject`,
+    /// in which case a `ScheduleConflict` is returned.
+    /// 
+    /// Events that fail `NewWeekEvent::validate` are never stored, and neither are their new rooms.
+    pub fn add_event(&mut self, mut new_event: NewWeekEvent, policy: ConflictPolicy) -> Result<()>{
+        new_event.validate()?;
+        self.transaction(|m| {
+            m.assign_term(&mut new_event)?;
+            m.assign_room(&mut new_event)?;
+            m.assign_course(&mut new_event)?;
+            let existing = m.by_day(new_event.day)?;
+            let candidate = new_event.clone().into_week_event(None);
+            Self::check_room(&existing, &candidate)?;
+            if policy == ConflictPolicy::Reject {
+                Self::check_conflicts(&existing, &candidate)?;
+            }
+            let new_event: NewEvent = new_event.into();
+            diesel::insert_into(events::table)
+                .values(new_event)
+                .execute(&mut m.connection)?;
+            Ok(())
+        })
+    }
     pub fn delete_event(&mut self, event_id: i32) -> Result<()> {
         diesel::delete(events.filter(id.eq(event_id)))
             .execute(&mut self.connection)?;
         Ok(())
     }
+    ///Fails naming the ids among `ids` that don't belong to any event.
+    pub fn check_events(&mut self, ids: &[i32]) -> Result<()> {
+        let found = events.filter(id.eq_any(ids))
+            .select(id)
+            .load::<Option<i32>>(&mut self.connection)?;
+        let mut unknown = ids.iter()
+            .filter(|i| !found.contains(&Some(**i)))
+            .collect::<Vec<&i32>>();
+        if !unknown.is_empty() {
+            unknown.sort();
+            unknown.dedup();
+            let unknown = unknown.iter().map(|i| i.to_string()).collect::<Vec<String>>();
+            return Err(anyhow::anyhow!("no entries with ids {}", unknown.join(", ")));
+        }
+        Ok(())
+    }
+    ///Deletes several events at once, returns how many were deleted.
+    /// 
+    /// Nothing is deleted if any of the ids doesn't belong to an event, see `check_events`.
+    pub fn delete_events(&mut self, ids: &[i32]) -> Result<usize> {
+        self.transaction(|m| {
+            m.check_events(ids)?;
+            Ok(diesel::delete(events.filter(id.eq_any(ids)))
+                .execute(&mut m.connection)?)
+        })
+    }
     pub fn get_event(&mut self, event_id: i32) -> Result<WeekEvent> {
         let event = events.filter(id.eq(Some(event_id)))
             .first::<Event>(&mut self.connection)?;
-        Ok(event.into())
+        Ok(self.with_details(vec![event])?.remove(0))
     }
 
     // pub fn get_events(&mut self, event: WeekEvent) -> Result<Vec<WeekEvent>>{
@@ -66,7 +200,6 @@ impl EventsManager {
     //         .or_filter(day.eq(event.day))
     //         .or_filter(starth.eq(event.starth))
     //         .or_filter(endh.eq(event.endh))
-    //         .or_filter(isLecture.eq(event.is_lecture))
     //         .load::<Event>(&mut self.connection)?;
 
     //     let ret: Vec<WeekEvent> = found.into_iter()
@@ -75,70 +208,742 @@ impl EventsManager {
 
     //     Ok(ret)
     // }
+    ///Query over the events visible in the active term, which are its own events and the ones 
+    /// that don't belong to any term. All events are visible when no term is active.
+    fn scoped(&mut self) -> Result<events::BoxedQuery<'static, diesel::sqlite::Sqlite>> {
+        let mut query = events::table.into_boxed();
+        if let Some(term) = self.active_term()? {
+            query = query.filter(term_id.eq(term.id).or(term_id.is_null()));
+        }
+        Ok(query)
+    }
+    ///Turns loaded rows into `WeekEvent`s, filling in their rooms, instructors, courses, tags 
+    /// and notes.
+    fn with_details(&mut self, rows: Vec<Event>) -> Result<Vec<WeekEvent>> {
+        use schema::event_instructors::dsl as ei;
+        use schema::event_tags::dsl as et;
+        use schema::notes::dsl as n;
+        let ids = rows.iter().filter_map(|e| e.id).collect::<Vec<i32>>();
+        let rooms = self.get_rooms()?;
+        let courses = self.get_courses()?;
+        let instructors = self.get_instructors()?;
+        let links = ei::event_instructors
+            .filter(ei::event_id.eq_any(&ids))
+            .load::<EventInstructor>(&mut self.connection)?;
+        let tags = et::event_tags
+            .filter(et::event_id.eq_any(&ids))
+            .order(et::tag)
+            .load::<EventTag>(&mut self.connection)?;
+        let notes = n::notes
+            .filter(n::event_id.eq_any(&ids))
+            .order(n::id)
+            .load::<NoteRow>(&mut self.connection)?;
+        Ok(rows.into_iter()
+            .map(|e| {
+                let room = e.room_id
+                    .and_then(|r| rooms.iter().find(|room| room.id == Some(r)).cloned());
+                let taught_by = instructors.iter()
+                    .filter(|i| links.iter()
+                        .any(|l| Some(l.event_id) == e.id && Some(l.instructor_id) == i.id))
+                    .cloned()
+                    .collect();
+                let course = e.course_id
+                    .and_then(|c| courses.iter().find(|course| course.id == Some(c)).cloned());
+                let tagged = tags.iter()
+                    .filter(|t| Some(t.event_id) == e.id)
+                    .map(|t| t.tag.clone())
+                    .collect();
+                let noted = notes.iter()
+                    .filter(|n| Some(n.event_id) == e.id)
+                    .map(|n| Note { id: n.id, event_id: n.event_id, text: n.body.clone() })
+                    .collect();
+                WeekEvent { room, instructors: taught_by, course, tags: tagged, notes: noted, ..e.into() }
+            })
+            .collect())
+    }
+    ///Stores the event's room if it isn't in the database yet, so it can be referenced.
+    fn assign_room(&mut self, event: &mut NewWeekEvent) -> Result<()> {
+        if let Some(room) = event.room.take() {
+            event.room = Some(self.resolve_room(room)?);
+        }
+        Ok(())
+    }
+    ///Looks up the event's course by code if it doesn't have an id.
+    fn assign_course(&mut self, event: &mut NewWeekEvent) -> Result<()> {
+        if let Some(course) = event.course.take() {
+            event.course = Some(match course.id {
+                Some(_) => course,
+                None => self.get_course(&course.code)?,
+            });
+        }
+        Ok(())
+    }
+    fn assign_term(&mut self, event: &mut NewWeekEvent) -> Result<()> {
+        if event.term_id.is_none() {
+            event.term_id = self.active_term()?.map(|t| t.id);
+        }
+        Ok(())
+    }
     pub fn by_day(&mut self, weekday: chrono::Weekday) -> Result<Vec<WeekEvent>>{
-        let found = events.filter(day.eq(weekday.to_string()))
+        let found = self.scoped()?.filter(day.eq(weekday.to_string()))
             .load::<Event>(&mut self.connection)?;
-        Ok(found.into_iter().map(|e| e.into()).collect::<Vec<WeekEvent>>())
+        self.with_details(found)
+    }
+    ///Events that take place on the date `on`, taking their `Recurrence` into account, sorted by start hour.
+    /// 
+    /// Events that belong to a term only take place between its start and end dates. Nothing 
+    /// takes place on holidays, and cancelled or moved events are left out or moved.
+    pub fn by_date(&mut self, on: chrono::NaiveDate) -> Result<Vec<WeekEvent>> {
+        use chrono::Datelike;
+        if self.holiday_on(on)?.is_some() {
+            return Ok(vec![]);
+        }
+        let terms = self.get_terms()?;
+        let exceptions = self.exceptions_on(on)?;
+        let event_s = self.by_day(on.weekday())?;
+        Ok(Self::occurences(&event_s, &terms, &exceptions, on))
+    }
+    ///The events among `event_s` that take place on `on`, which mustn't be a holiday, see `by_date`.
+    fn occurences(event_s: &[WeekEvent], terms: &[Term], exceptions: &[EventException], on: chrono::NaiveDate) -> Vec<WeekEvent> {
+        let in_term = |e: &WeekEvent| match e.term_id {
+            Some(t) => terms.iter().any(|term| term.id == t && term.contains(on)),
+            None => true,
+        };
+        let mut ret = event_s.iter()
+            .filter(|e| e.occurs_on(on) && in_term(e))
+            .filter_map(|e| match exceptions.iter().find(|x| Some(x.event_id) == e.id && x.date == on) {
+                Some(x) => x.apply(e.clone()),
+                None => Some(e.clone()),
+            })
+            .collect::<Vec<WeekEvent>>();
+        ret.sort_by_key(|e| (e.starth, e.endh));
+        ret
+    }
+    ///Events taking place in the week, Monday to Sunday, that `on` falls in. See `by_date`.
+    pub fn by_week(&mut self, on: chrono::NaiveDate) -> Result<Vec<WeekEvent>> {
+        let mut ret = vec![];
+        for week_day in recurrence::week_start(on).iter_days().take(7) {
+            ret.append(&mut self.by_date(week_day)?);
+        }
+        Ok(ret)
+    }
+    ///Events taking place at `at`, sorted by start hour.
+    pub fn happening_at(&mut self, at: chrono::NaiveDateTime) -> Result<Vec<WeekEvent>> {
+        Ok(self.by_date(at.date())?
+            .into_iter()
+            .filter(|e| e.starth <= at.time() && at.time() < e.endh)
+            .collect())
+    }
+    ///The first `amount` events starting after `after`, paired with the date they take place on 
+    /// and sorted by when they start. Looks up to a year ahead.
+    pub fn upcoming(&mut self, after: chrono::NaiveDateTime, amount: usize) -> Result<Vec<(chrono::NaiveDate, WeekEvent)>> {
+        let mut ret = vec![];
+        let event_s = self.get_all()?;
+        if event_s.is_empty() {
+            return Ok(ret);
+        }
+        let terms = self.get_terms()?;
+        let exceptions = self.get_exceptions()?;
+        let holidays = self.get_holidays()?;
+        for on in after.date().iter_days().take(LOOKAHEAD_DAYS) {
+            if ret.len() >= amount {
+                break;
+            }
+            if holidays.iter().any(|h| h.date == on) {
+                continue;
+            }
+            ret.extend(Self::occurences(&event_s, &terms, &exceptions, on)
+                .into_iter()
+                .filter(|e| on > after.date() || e.starth > after.time())
+                .map(|e| (on, e)));
+        }
+        ret.truncate(amount);
+        Ok(ret)
     }
     pub fn by_starth(&mut self, hour: chrono::NaiveTime) -> Result<Vec<WeekEvent>> {
-        Ok(events.filter(starth.eq(hour.to_string()))
-                    .load::<Event>(&mut self.connection)?
-                    .into_iter()
-                    .map(|e| e.into())
-                    .collect::<Vec<WeekEvent>>()
-                    .into())
+        let found = self.scoped()?.filter(starth.eq(hour.to_string()))
+            .load::<Event>(&mut self.connection)?;
+        self.with_details(found)
     }
     pub fn by_endh(&mut self, hour: chrono::NaiveTime) -> Result<Vec<WeekEvent>> {
-        Ok(events.filter(endh.eq(hour.to_string()))
-                .load::<Event>(&mut self.connection)?
-                .into_iter()
-                .map(|e| e.into())
-                .collect::<Vec<WeekEvent>>()
-                .into())
+        let found = self.scoped()?.filter(endh.eq(hour.to_string()))
+            .load::<Event>(&mut self.connection)?;
+        self.with_details(found)
     }
     pub fn by_name(&mut self, n: String) -> Result<Vec<WeekEvent>> {
-        Ok(events.filter(name.eq(n))
-                .load::<Event>(&mut self.connection)?
-                .into_iter()
-                .map(|e| e.into())
-                .collect::<Vec<WeekEvent>>()
-                .into())
+        let found = self.scoped()?.filter(name.eq(n))
+            .load::<Event>(&mut self.connection)?;
+        self.with_details(found)
     }
-    pub fn by_is_lecture(&mut self, val: bool) -> Result<Vec<WeekEvent>> {
-        Ok(events.filter(isLecture.eq(val as i32))
-                .load::<Event>(&mut self.connection)?
-                .into_iter()
-                .map(|e| e.into())
-                .collect::<Vec<WeekEvent>>()
-                .into())
+    pub fn by_kind(&mut self, k: EventKind) -> Result<Vec<WeekEvent>> {
+        let found = self.scoped()?.filter(kind.eq(k.to_string()))
+            .load::<Event>(&mut self.connection)?;
+        self.with_details(found)
     }
     pub fn get_all(&mut self) -> Result<Vec<WeekEvent>> {
         let res = 
-            events.load::<Event>(&mut self.connection)?;
-
-        let res = res.into_iter()
-            .map(|e| e.into())
-            .collect::<Vec<WeekEvent>>();
-        Ok(res)
+            self.scoped()?.load::<Event>(&mut self.connection)?;
+        self.with_details(res)
     }
-    pub fn add_events(&mut self, event_s: Vec<NewWeekEvent>) -> Result<()> {
-        let event_s: Vec<NewEvent> = event_s.into_iter()
-            .map(|e| e.into())
-            .collect();
+    ///Stores several events at once, see `add_event`.
+    /// 
+    /// With `ConflictPolicy::Reject` the new events may neither overlap stored events nor each other.
+    /// If any of them is rejected, nothing is stored, including new rooms.
+    pub fn add_events(&mut self, mut event_s: Vec<NewWeekEvent>, policy: ConflictPolicy) -> Result<()> {
+        self.transaction(|m| {
+            for e in event_s.iter_mut() {
+                e.validate()?;
+                m.assign_term(e)?;
+                m.assign_room(e)?;
+                m.assign_course(e)?;
+            }
+            let mut existing = m.get_all()?;
+            for e in &event_s {
+                let e = e.clone().into_week_event(None);
+                Self::check_room(&existing, &e)?;
+                if policy == ConflictPolicy::Reject {
+                    Self::check_conflicts(&existing, &e)?;
+                }
+                existing.push(e);
+            }
+            let event_s: Vec<NewEvent> = event_s.into_iter()
+                .map(|e| e.into())
+                .collect();
 
-        diesel::insert_into(events::table)
-            .values(event_s)
-            .execute(&mut self.connection)?;
+            diesel::insert_into(events::table)
+                .values(event_s)
+                .execute(&mut m.connection)?;
 
-        Ok(())
+            Ok(())
+        })
     }
-    pub fn change_event(&mut self, updated_event: models::UpdatedWeekEvent) -> Result<()>{
+    ///Applies changes to a stored event, with the same validation and conflict checks 
+    /// as `add_event` performed on the changed event.
+    /// 
+    /// A new room given in `UpdatedWeekEvent::room` is only stored if the change is accepted.
+    pub fn change_event(&mut self, 
+        mut updated_event: models::UpdatedWeekEvent, 
+        policy: ConflictPolicy) -> Result<()>{
+        self.transaction(|m| {
+            if let Some(room) = updated_event.room.take() {
+                updated_event.room_id = Some(m.resolve_room(room)?.id);
+            }
+            let mut merged = m.get_event(updated_event.id)?.updated(&updated_event)?;
+            merged.validate()?;
+            // `updated` only knows the room's and course's ids
+            if let Some(room) = updated_event.room_id {
+                merged.room = room.map(|r| m.get_room(r)).transpose()?;
+            }
+            if let Some(course) = updated_event.course_id {
+                merged.course = course.map(|c| m.get_course_by_id(c)).transpose()?;
+            }
+            let existing = m.by_day(merged.day)?;
+            Self::check_room(&existing, &merged)?;
+            if policy == ConflictPolicy::Reject {
+                Self::check_conflicts(&existing, &merged)?;
+            }
             diesel::update(events::table)
                 .filter(id.eq(updated_event.id))
                 .set(&updated_event)
-                .execute(&mut self.connection)?;
+                .execute(&mut m.connection)?;
+            Ok(())
+        })
+    }
+    pub fn add_term(&mut self, term: NewTerm) -> Result<()> {
+        term.validate()?;
+        diesel::insert_into(schema::terms::table)
+            .values(NewTermRow::from(term))
+            .execute(&mut self.connection)?;
+        Ok(())
+    }
+    pub fn get_terms(&mut self) -> Result<Vec<Term>> {
+        use schema::terms::dsl as t;
+        Ok(t::terms.order(t::start_date)
+            .load::<TermRow>(&mut self.connection)?
+            .into_iter()
+            .map(|t| t.into())
+            .collect())
+    }
+    ///The term that queries are scoped to, if any.
+    pub fn active_term(&mut self) -> Result<Option<Term>> {
+        use schema::terms::dsl as t;
+        Ok(t::terms.filter(t::active.ne(0))
+            .first::<TermRow>(&mut self.connection)
+            .optional()?
+            .map(|t| t.into()))
+    }
+    ///Makes the term with `term` id the active one, or deactivates all terms if `term` is `None`.
+    pub fn set_active_term(&mut self, term: Option<i32>) -> Result<()> {
+        use schema::terms::dsl as t;
+        self.transaction(|m| {
+            diesel::update(t::terms)
+                .set(t::active.eq(0))
+                .execute(&mut m.connection)?;
+            if let Some(term) = term {
+                let updated = diesel::update(t::terms.filter(t::id.eq(term)))
+                    .set(t::active.eq(1))
+                    .execute(&mut m.connection)?;
+                if updated == 0 {
+                    return Err(anyhow::anyhow!("term with id {} not found", term));
+                }
+            }
+            Ok(())
+        })
+    }
+    ///Moves all events that don't belong to any term into the term with `term` id.
+    pub fn adopt_events(&mut self, term: i32) -> Result<usize> {
+        Ok(diesel::update(events.filter(term_id.is_null()))
+            .set(term_id.eq(term))
+            .execute(&mut self.connection)?)
+    }
+    ///Cancels or moves a single occurence of an event, replacing the exception it already 
+    /// has on that date. The room an event is moved to is stored if it isn't yet.
+    pub fn add_exception(&mut self, mut exception: EventException) -> Result<()> {
+        exception.validate()?;
+        let event = self.get_event(exception.event_id)?;
+        if !event.occurs_on(exception.date) {
+            return Err(anyhow::anyhow!("`{}` doesn't take place on {}", event.name, exception.date));
+        }
+        self.transaction(|m| {
+            if let Change::Moved { room, .. } = &mut exception.change {
+                *room = room.take().map(|r| m.resolve_room(r)).transpose()?;
+            }
+            m.remove_exception(exception.event_id, exception.date)?;
+            diesel::insert_into(schema::exceptions::table)
+                .values(NewExceptionRow::from(exception))
+                .execute(&mut m.connection)?;
+            Ok(())
+        })
+    }
+    ///Restores the occurence of an event on the date `on`, returns whether it had an exception.
+    pub fn remove_exception(&mut self, event: i32, on: chrono::NaiveDate) -> Result<bool> {
+        use schema::exceptions::dsl as x;
+        let removed = diesel::delete(x::exceptions
+                .filter(x::event_id.eq(event))
+                .filter(x::date.eq(on.to_string())))
+            .execute(&mut self.connection)?;
+        Ok(removed > 0)
+    }
+    pub fn get_exceptions(&mut self) -> Result<Vec<EventException>> {
+        use schema::exceptions::dsl as x;
+        Ok(x::exceptions.left_join(schema::rooms::table)
+            .order((x::date, x::event_id))
+            .load::<(ExceptionRow, Option<RoomRow>)>(&mut self.connection)?
+            .into_iter()
+            .map(|x| x.into())
+            .collect())
+    }
+    pub fn exceptions_on(&mut self, on: chrono::NaiveDate) -> Result<Vec<EventException>> {
+        use schema::exceptions::dsl as x;
+        Ok(x::exceptions.left_join(schema::rooms::table)
+            .filter(x::date.eq(on.to_string()))
+            .load::<(ExceptionRow, Option<RoomRow>)>(&mut self.connection)?
+            .into_iter()
+            .map(|x| x.into())
+            .collect())
+    }
+    pub fn add_holiday(&mut self, holiday: Holiday) -> Result<()> {
+        holiday.validate()?;
+        diesel::insert_into(schema::holidays::table)
+            .values(NewHolidayRow::from(holiday))
+            .execute(&mut self.connection)?;
+        Ok(())
+    }
+    ///Adds a holiday called `name` on every day of `range`, returns how many days were added.
+    /// 
+    /// Nothing is stored if any of the days already is a holiday.
+    pub fn add_holidays(&mut self, range: std::ops::RangeInclusive<chrono::NaiveDate>, holiday_name: &str) -> Result<usize> {
+        let (start, end) = (*range.start(), *range.end());
+        if end < start {
+            return Err(ValidationError::InvalidHolidayRange { start, end }.into());
+        }
+        self.transaction(|m| {
+            let mut count = 0;
+            for on in start.iter_days().take_while(|d| *d <= end) {
+                m.add_holiday(Holiday { id: None, date: on, name: holiday_name.to_owned() })?;
+                count += 1;
+            }
+            Ok(count)
+        })
+    }
+    ///Returns whether there was a holiday on the date `on`.
+    pub fn remove_holiday(&mut self, on: chrono::NaiveDate) -> Result<bool> {
+        use schema::holidays::dsl as h;
+        let removed = diesel::delete(h::holidays.filter(h::date.eq(on.to_string())))
+            .execute(&mut self.connection)?;
+        Ok(removed > 0)
+    }
+    pub fn get_holidays(&mut self) -> Result<Vec<Holiday>> {
+        use schema::holidays::dsl as h;
+        Ok(h::holidays.order(h::date)
+            .load::<HolidayRow>(&mut self.connection)?
+            .into_iter()
+            .map(|h| h.into())
+            .collect())
+    }
+    pub fn holiday_on(&mut self, on: chrono::NaiveDate) -> Result<Option<Holiday>> {
+        use schema::holidays::dsl as h;
+        Ok(h::holidays.filter(h::date.eq(on.to_string()))
+            .first::<HolidayRow>(&mut self.connection)
+            .optional()?
+            .map(|h| h.into()))
+    }
+    ///Returns the stored room with the same building and number as `room`, storing it first if 
+    /// there's none yet.
+    pub fn resolve_room(&mut self, room: Room) -> Result<Room> {
+        use schema::rooms::dsl as r;
+        if room.id.is_some() {
+            return Ok(room);
+        }
+        room.validate()?;
+        let find = |m: &mut Self| r::rooms
+            .filter(r::building.eq(&room.building))
+            .filter(r::number.eq(&room.number))
+            .first::<RoomRow>(&mut m.connection)
+            .optional();
+        if let Some(found) = find(self)? {
+            return Ok(found.into());
+        }
+        diesel::insert_into(r::rooms)
+            .values(NewRoomRow::from(room.clone()))
+            .execute(&mut self.connection)?;
+        Ok(find(self)?.ok_or_else(|| anyhow::anyhow!("room `{}` was not stored", room))?.into())
+    }
+    ///Stores a room, or replaces the notes of an existing one.
+    pub fn add_room(&mut self, room: Room) -> Result<Room> {
+        use schema::rooms::dsl as r;
+        let notes = room.notes.clone();
+        let stored = self.resolve_room(Room { id: None, ..room })?;
+        diesel::update(r::rooms.filter(r::id.eq(stored.id)))
+            .set(r::notes.eq(&notes))
+            .execute(&mut self.connection)?;
+        Ok(Room { notes, ..stored })
+    }
+    pub fn get_room(&mut self, room: i32) -> Result<Room> {
+        use schema::rooms::dsl as r;
+        Ok(r::rooms.filter(r::id.eq(room))
+            .first::<RoomRow>(&mut self.connection)?
+            .into())
+    }
+    pub fn get_rooms(&mut self) -> Result<Vec<Room>> {
+        use schema::rooms::dsl as r;
+        Ok(r::rooms.order((r::building, r::number))
+            .load::<RoomRow>(&mut self.connection)?
+            .into_iter()
+            .map(|r| r.into())
+            .collect())
+    }
+    ///Deletes a room, events that took place in it are left without a room.
+    pub fn delete_room(&mut self, room: i32) -> Result<()> {
+        use schema::rooms::dsl as r;
+        diesel::delete(r::rooms.filter(r::id.eq(room)))
+            .execute(&mut self.connection)?;
+        Ok(())
+    }
+    ///Events taking place in rooms that match `query`, see `Room::matches`.
+    pub fn by_room(&mut self, query: &str) -> Result<Vec<WeekEvent>> {
+        let ids = self.get_rooms()?
+            .into_iter()
+            .filter(|r| r.matches(query))
+            .filter_map(|r| r.id)
+            .collect::<Vec<i32>>();
+        let found = self.scoped()?.filter(room_id.eq_any(ids))
+            .load::<Event>(&mut self.connection)?;
+        self.with_details(found)
+    }
+    ///Stores a course, or updates the one with the same code, fields left as `None` keep 
+    /// their stored value.
+    pub fn add_course(&mut self, course: Course) -> Result<Course> {
+        use schema::courses::dsl as c;
+        course.validate()?;
+        let row = NewCourseRow::from(course.clone());
+        self.transaction(|m| {
+            let updated = diesel::update(c::courses.filter(c::code.eq(&row.code)))
+                .set(&row)
+                .execute(&mut m.connection)?;
+            if updated == 0 {
+                diesel::insert_into(c::courses)
+                    .values(&row)
+                    .execute(&mut m.connection)?;
+            }
+            Ok(())
+        })?;
+        self.get_course(&course.code)
+    }
+    pub fn get_courses(&mut self) -> Result<Vec<Course>> {
+        use schema::courses::dsl as c;
+        Ok(c::courses.order(c::code)
+            .load::<CourseRow>(&mut self.connection)?
+            .into_iter()
+            .map(|c| c.into())
+            .collect())
+    }
+    ///Finds a course by code, ignoring case.
+    pub fn get_course(&mut self, code: &str) -> Result<Course> {
+        self.get_courses()?
+            .into_iter()
+            .find(|c| c.code.eq_ignore_ascii_case(code.trim()))
+            .ok_or_else(|| anyhow::anyhow!("course `{}` not found", code))
+    }
+    pub fn get_course_by_id(&mut self, course: i32) -> Result<Course> {
+        use schema::courses::dsl as c;
+        Ok(c::courses.filter(c::id.eq(course))
+            .first::<CourseRow>(&mut self.connection)?
+            .into())
+    }
+    ///Deletes a course, its sessions are kept without a course.
+    pub fn delete_course(&mut self, course: i32) -> Result<()> {
+        use schema::courses::dsl as c;
+        diesel::delete(c::courses.filter(c::id.eq(course)))
+            .execute(&mut self.connection)?;
+        Ok(())
+    }
+    ///Sessions of the course with `code`, sorted by day and start hour.
+    pub fn by_course(&mut self, code: &str) -> Result<Vec<WeekEvent>> {
+        let course = self.get_course(code)?;
+        let found = self.scoped()?.filter(course_id.eq(course.id))
+            .load::<Event>(&mut self.connection)?;
+        let mut ret = self.with_details(found)?;
+        ret.sort_by_key(|e| (e.date, e.day.num_days_from_monday(), e.starth));
+        Ok(ret)
+    }
+    ///Links every event called `event_name` to `course`, returns how many were linked.
+    pub fn link_course(&mut self, course: i32, event_name: &str) -> Result<usize> {
+        Ok(diesel::update(events.filter(name.eq(event_name)))
+            .set(course_id.eq(course))
+            .execute(&mut self.connection)?)
+    }
+    ///Stores an instructor, or updates the contact info of the one with the same name, fields
+    /// left as `None` keep their stored value.
+    pub fn add_instructor(&mut self, instructor: Instructor) -> Result<Instructor> {
+        use schema::instructors::dsl as i;
+        instructor.validate()?;
+        let row = NewInstructorRow::from(instructor.clone());
+        self.transaction(|m| {
+            let updated = diesel::update(i::instructors.filter(i::name.eq(&row.name)))
+                .set(&row)
+                .execute(&mut m.connection)?;
+            if updated == 0 {
+                diesel::insert_into(i::instructors)
+                    .values(&row)
+                    .execute(&mut m.connection)?;
+            }
+            Ok(())
+        })?;
+        self.get_instructor(&instructor.name)
+    }
+    pub fn get_instructors(&mut self) -> Result<Vec<Instructor>> {
+        use schema::instructors::dsl as i;
+        Ok(i::instructors.order(i::name)
+            .load::<InstructorRow>(&mut self.connection)?
+            .into_iter()
+            .map(|i| i.into())
+            .collect())
+    }
+    ///Finds an instructor by name, ignoring case.
+    pub fn get_instructor(&mut self, instructor: &str) -> Result<Instructor> {
+        self.get_instructors()?
+            .into_iter()
+            .find(|i| i.name.eq_ignore_ascii_case(instructor.trim()))
+            .ok_or_else(|| anyhow::anyhow!("instructor `{}` not found", instructor))
+    }
+    pub fn delete_instructor(&mut self, instructor: i32) -> Result<()> {
+        use schema::instructors::dsl as i;
+        diesel::delete(i::instructors.filter(i::id.eq(instructor)))
+            .execute(&mut self.connection)?;
+        Ok(())
+    }
+    ///Makes `instructor` one of the people teaching `event`.
+    pub fn assign_instructor(&mut self, event: i32, instructor: i32) -> Result<()> {
+        use schema::event_instructors::dsl as ei;
+        diesel::insert_or_ignore_into(ei::event_instructors)
+            .values(EventInstructor { event_id: event, instructor_id: instructor })
+            .execute(&mut self.connection)?;
+        Ok(())
+    }
+    ///Returns whether `instructor` was teaching `event`.
+    pub fn unassign_instructor(&mut self, event: i32, instructor: i32) -> Result<bool> {
+        use schema::event_instructors::dsl as ei;
+        let removed = diesel::delete(ei::event_instructors
+                .filter(ei::event_id.eq(event))
+                .filter(ei::instructor_id.eq(instructor)))
+            .execute(&mut self.connection)?;
+        Ok(removed > 0)
+    }
+    ///Events taught by instructors whose name contains `query`, ignoring case.
+    pub fn by_instructor(&mut self, query: &str) -> Result<Vec<WeekEvent>> {
+        let query = query.trim().to_lowercase();
+        Ok(self.get_all()?
+            .into_iter()
+            .filter(|e| e.instructors.iter().any(|i| i.name.to_lowercase().contains(&query)))
+            .collect())
+    }
+    ///Tags `event`, returns whether it wasn't tagged with `tag` already, ignoring case.
+    pub fn add_tag(&mut self, event: i32, tag: &str) -> Result<bool> {
+        use schema::event_tags::dsl as et;
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(ValidationError::EmptyText.into());
+        }
+        let added = diesel::insert_or_ignore_into(et::event_tags)
+            .values(EventTag { event_id: event, tag: tag.to_owned() })
+            .execute(&mut self.connection)?;
+        Ok(added > 0)
+    }
+    ///Returns whether `event` was tagged with `tag`, ignoring case.
+    pub fn remove_tag(&mut self, event: i32, tag: &str) -> Result<bool> {
+        use schema::event_tags::dsl as et;
+        let removed = diesel::delete(et::event_tags
+                .filter(et::event_id.eq(event))
+                .filter(et::tag.eq(tag.trim())))
+            .execute(&mut self.connection)?;
+        Ok(removed > 0)
+    }
+    ///Events tagged with `tag`, ignoring case, sorted by day and start hour.
+    pub fn by_tag(&mut self, tag: &str) -> Result<Vec<WeekEvent>> {
+        use schema::event_tags::dsl as et;
+        let tagged = et::event_tags
+            .filter(et::tag.eq(tag.trim()))
+            .select(et::event_id)
+            .load::<i32>(&mut self.connection)?;
+        let found = self.scoped()?.filter(id.eq_any(tagged.into_iter().map(Some)))
+            .load::<Event>(&mut self.connection)?;
+        let mut ret = self.with_details(found)?;
+        ret.sort_by_key(|e| (e.date, e.day.num_days_from_monday(), e.starth));
+        Ok(ret)
+    }
+    ///Attaches a note to its event and returns it with its id.
+    pub fn add_note(&mut self, note: Note) -> Result<Note> {
+        use schema::notes::dsl as n;
+        note.validate()?;
+        self.transaction(|m| {
+            diesel::insert_into(n::notes)
+                .values(NewNoteRow::from(note))
+                .execute(&mut m.connection)?;
+            Ok(n::notes.order(n::id.desc())
+                .first::<NoteRow>(&mut m.connection)?
+                .into())
+        })
+    }
+    ///Returns whether a note with `note_id` existed.
+    pub fn remove_note(&mut self, note_id: i32) -> Result<bool> {
+        use schema::notes::dsl as n;
+        let removed = diesel::delete(n::notes.filter(n::id.eq(note_id)))
+            .execute(&mut self.connection)?;
+        Ok(removed > 0)
+    }
+    ///Finds gaps of at least `min_duration` between events, within the `window` of each day.
+    /// 
+    /// Looks at a single day, or the whole week if `weekday` is `None`, of the weekly timetable
+    /// so one-off events are left out. Slots are sorted from the longest to the shortest.
+    pub fn free_slots(&mut self, 
+        weekday: Option<chrono::Weekday>, 
+        min_duration: chrono::Duration, 
+        window: (chrono::NaiveTime, chrono::NaiveTime)) -> Result<Vec<slots::FreeSlot>> {
+        use chrono::Weekday::*;
+        let days = match weekday {
+            Some(d) => vec![d],
+            None => vec![Mon, Tue, Wed, Thu, Fri, Sat, Sun],
+        };
+        let event_s = self.get_all()?
+            .into_iter()
+            .filter(|e| e.date.is_none())
+            .collect::<Vec<WeekEvent>>();
+        let mut ret = vec![];
+        for d in days {
+            ret.extend(slots::free_slots(&event_s, d, min_duration, window));
+        }
+        slots::sort_by_length(&mut ret);
+        Ok(ret)
+    }
+    ///Finds every pair of overlapping events in the timetable.
+    pub fn find_conflicts(&mut self) -> Result<Vec<(WeekEvent, WeekEvent)>> {
+        Ok(conflict::find_all(&self.get_all()?))
+    }
+    fn check_conflicts(existing: &[WeekEvent], event: &WeekEvent) -> Result<()> {
+        let conflicts = conflict::clashing(existing, event);
+        if !conflicts.is_empty() {
+            return Err(ScheduleConflict {
+                name: event.name.clone(),
+                day: event.day,
+                starth: event.starth,
+                endh: event.endh,
+                conflicts,
+            }.into());
+        }
+        Ok(())
+    }
+    ///Fails with a `RoomConflict` if `event` would share its room with another event at the same time.
+    fn check_room(existing: &[WeekEvent], event: &WeekEvent) -> Result<()> {
+        let conflicts = conflict::room_clashing(existing, event);
+        if let (Some(room), false) = (&event.room, conflicts.is_empty()) {
+            return Err(RoomConflict {
+                room: room.clone(),
+                name: event.name.clone(),
+                conflicts,
+            }.into());
+        }
         Ok(())
     }
+    ///Renders the whole timetable as an iCalendar document, with every event
+    /// recurring weekly from `semester_start` onwards, in the timetable's timezone.
+    pub fn export_ics(&mut self, semester_start: chrono::NaiveDate) -> Result<String> {
+        let tz = self.timezone()?;
+        let mut event_s = self.get_all()?;
+        event_s.sort_by_key(|e| (e.day.num_days_from_monday(), e.starth));
+        Ok(ics::export(&event_s, semester_start, tz))
+    }
+    ///Imports weekly recurring events from an iCalendar document.
+    /// 
+    /// All importable events are inserted in a single transaction, events that could not be
+    /// mapped onto the timetable, are invalid or overlap other events are returned in `IcsImport::skipped`.
+    pub fn import_ics(&mut self, calendar: &str) -> Result<ics::IcsImport> {
+        let tz = self.timezone()?;
+        let mut imported = ics::import(calendar, tz)?;
+        let mut existing = self.get_all()?;
+        let mut accepted = vec![];
+        for e in imported.events {
+            let candidate = e.clone().into_week_event(None);
+            let checked = e.validate()
+                .map_err(anyhow::Error::from)
+                .and_then(|_| Self::check_conflicts(&existing, &candidate));
+            match checked {
+                Result::Ok(()) => {
+                    existing.push(candidate);
+                    accepted.push(e);
+                },
+                Err(err) => imported.skipped.push(ics::SkippedEvent {
+                    summary: e.name,
+                    reason: err.to_string(),
+                }),
+            }
+        }
+        imported.events = accepted;
+        if !imported.events.is_empty() {
+            let event_s = imported.events.clone();
+            self.transaction(|m| m.add_events(event_s, ConflictPolicy::Allow))?;
+        }
+        Ok(imported)
+    }
+    ///Writes the whole timetable as CSV.
+    pub fn export_csv<W: std::io::Write>(&mut self, writer: W) -> Result<()> {
+        let mut event_s = self.get_all()?;
+        event_s.sort_by_key(|e| (e.day.num_days_from_monday(), e.starth));
+        csv_io::export(&event_s, writer)
+    }
+    ///Reads events from CSV and inserts them in a single transaction.
+    /// 
+    /// Nothing is inserted if any row is invalid, the returned `csv_io::CsvImportError`
+    /// names the line and column of every field that failed to parse.
+    /// Events that overlap each other or stored events are rejected with a `ScheduleConflict`.
+    pub fn import_csv<R: std::io::Read>(&mut self, reader: R) -> Result<usize> {
+        let event_s = csv_io::import(reader)?;
+        let count = event_s.len();
+        if count > 0 {
+            self.transaction(|m| m.add_events(event_s, ConflictPolicy::Reject))?;
+        }
+        Ok(count)
+    }
 
 }
 
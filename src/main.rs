@@ -1,6 +1,7 @@
 pub mod modules;
 
 use syd::commands::*;
+use syd::render::OutputFormat;
 use syd::*;
 
 fn main() {
@@ -9,12 +10,31 @@ fn main() {
     handler.add_module::<modules::GeneralModule>().unwrap();
     handler.add_module::<modules::TestModule>().unwrap();
     handler.add_module::<modules::GetModule>().unwrap();
-    use std::io;
+    handler.add_module::<modules::CsvModule>().unwrap();
+    handler.add_module::<modules::IcsModule>().unwrap();
+    handler.add_module::<modules::TermModule>().unwrap();
+    handler.add_module::<modules::RoomModule>().unwrap();
+    handler.add_module::<modules::InstructorModule>().unwrap();
+    handler.add_module::<modules::CourseModule>().unwrap();
+    handler.add_module::<modules::TagModule>().unwrap();
+    handler.add_module::<modules::NoteModule>().unwrap();
+    handler.add_module::<modules::ExceptionModule>().unwrap();
+    handler.add_module::<modules::HolidayModule>().unwrap();
+    use std::io::{self, IsTerminal};
+    if io::stdout().is_terminal() {
+        handler.set_output_format(OutputFormat::Color);
+    }
     startup_message();
     loop {
         println!("type a command...");
         let mut buff = String::from("");
         io::stdin().read_line(&mut buff).unwrap();
+        //an unclosed quote continues on the next line, e.g. for notes spanning several lines
+        while buff.matches('"').count() % 2 == 1 {
+            if io::stdin().read_line(&mut buff).unwrap() == 0 {
+                break;
+            }
+        }
         let buff = buff.trim();
         match buff {
             ".quit" => break,
@@ -23,12 +43,34 @@ fn main() {
                     print_command_info(&inf);
                 }
             },
+            _ if buff.starts_with(".format") => {
+                match buff.trim_start_matches(".format").trim().parse::<OutputFormat>() {
+                    Ok(format) => handler.set_output_format(format),
+                    Err(e) => println!("{}", e),
+                }
+            },
             _ => {
                 println!();
-                handler.handle(buff.into()).or_else(|e| {
-                    println!("{:?}", e);
-                    Ok::<_, &str>(())
-                }).unwrap_or_else(|err| println!("{}", err))
+                let output = handler.execute(buff.into())
+                    .and_then(|output| match output {
+                        CommandOutput::Confirm { prompt, command } => {
+                            println!("{} [y/n]", prompt);
+                            let mut answer = String::from("");
+                            io::stdin().read_line(&mut answer)?;
+                            if answer.trim() == "y" {
+                                handler.execute(command)
+                            } else {
+                                Ok(CommandOutput::None)
+                            }
+                        },
+                        output => Ok(output),
+                    })
+                    .and_then(|output| handler.render(&output));
+                match output {
+                    Ok(rendered) if rendered.is_empty() => {},
+                    Ok(rendered) => println!("{}", rendered),
+                    Err(e) => println!("{:?}", e),
+                }
             },
         }
     }
@@ -37,26 +79,12 @@ fn main() {
 fn startup_message() {
     println!("==|SYD 1.0|==");
     println!("type `.quit` to exit the program.");
-    println!("type `.commands` to get all commands.");
+    println!("type `.commands` to get all commands, or `help <command>` for one of them.");
+    println!("type `.format text`, `.format color` or `.format json` to change the output format.");
 }
 
 fn print_command_info(info: &syd::commands::CommandInfo) {
     println!();
-    print!("{} ", info.name);
-    
-    let mut sig = "(".to_owned();
-    for i in &info.args {
-        sig.push_str(format!("{}: {}, ", i.name, i.ty).as_str());
-    }
-    if let Some(index) = sig.rfind(','){
-        sig.remove(index);
-    }
-    print!("{}", sig);
-    print!(")");
-    
+    println!("{}", info.usage());
     println!("Description: {}", info.desc);
-    if let Some(g) = &info.group {
-        print!("{} ", g);
-    }
-    println!();
-}
\ No newline at end of file
+}
@@ -1,6 +1,6 @@
 use diesel::prelude::*;
 pub use super::schema;
-use schema::events;
+use schema::{courses, event_instructors, event_tags, events, exceptions, holidays, instructors, notes, rooms, terms};
 use diesel;
 
 pub mod transformed;
@@ -12,9 +12,13 @@ pub struct Event {
     pub day: String,
     pub starth: String,
     pub endh: String,
-    pub is_lecture: i32,
+    pub recurrence: String,
+    pub term_id: Option<i32>,
+    pub date: Option<String>,
+    pub kind: String,
+    pub room_id: Option<i32>,
+    pub course_id: Option<i32>,
 }
-#[allow(non_snake_case)]
 #[derive(Insertable, Clone)]
 #[diesel(table_name = events)]
 pub struct NewEvent {
@@ -22,11 +26,15 @@ pub struct NewEvent {
     pub day: String,
     pub starth: String,
     pub endh: String,
-    pub isLecture: i32,
+    pub recurrence: String,
+    pub term_id: Option<i32>,
+    pub date: Option<String>,
+    pub kind: String,
+    pub room_id: Option<i32>,
+    pub course_id: Option<i32>,
 }
 
-#[allow(non_snake_case)]
-#[derive(Debug, Clone, AsChangeset, Identifiable)]
+#[derive(Debug, Clone, Default, AsChangeset, Identifiable)]
 #[diesel(table_name = events)]
 pub struct UpdatedWeekEvent {
     pub id: i32,
@@ -34,6 +42,148 @@ pub struct UpdatedWeekEvent {
     pub day: Option<String>,
     pub starth: Option<String>,
     pub endh: Option<String>,
-    pub isLecture: Option<i32>,
+    pub recurrence: Option<String>,
+    pub term_id: Option<Option<i32>>,
+    pub date: Option<Option<String>>,
+    pub kind: Option<String>,
+    pub room_id: Option<Option<i32>>,
+    pub course_id: Option<Option<i32>>,
+    ///Room to move the event into, looked up or stored by `change_event` like the room of 
+    /// a `NewWeekEvent`. Replaces `room_id` when given.
+    #[diesel(skip_update)]
+    pub room: Option<transformed::Room>,
 }
 
+#[derive(Queryable)]
+pub struct TermRow {
+    pub id: Option<i32>,
+    pub name: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub active: i32,
+}
+
+#[derive(Insertable, Clone)]
+#[diesel(table_name = terms)]
+pub struct NewTermRow {
+    pub name: String,
+    pub start_date: String,
+    pub end_date: String,
+}
+
+
+#[derive(Queryable)]
+pub struct ExceptionRow {
+    pub id: Option<i32>,
+    pub event_id: i32,
+    pub date: String,
+    pub cancelled: i32,
+    pub starth: Option<String>,
+    pub endh: Option<String>,
+    pub room_id: Option<i32>,
+}
+
+#[derive(Insertable, Clone)]
+#[diesel(table_name = exceptions)]
+pub struct NewExceptionRow {
+    pub event_id: i32,
+    pub date: String,
+    pub cancelled: i32,
+    pub starth: Option<String>,
+    pub endh: Option<String>,
+    pub room_id: Option<i32>,
+}
+
+#[derive(Queryable)]
+pub struct HolidayRow {
+    pub id: Option<i32>,
+    pub date: String,
+    pub name: String,
+}
+
+#[derive(Insertable, Clone)]
+#[diesel(table_name = holidays)]
+pub struct NewHolidayRow {
+    pub date: String,
+    pub name: String,
+}
+
+#[derive(Queryable)]
+pub struct RoomRow {
+    pub id: Option<i32>,
+    pub building: String,
+    pub number: String,
+    pub notes: Option<String>,
+}
+
+#[derive(Insertable, Clone)]
+#[diesel(table_name = rooms)]
+pub struct NewRoomRow {
+    pub building: String,
+    pub number: String,
+    pub notes: Option<String>,
+}
+
+#[derive(Queryable)]
+pub struct InstructorRow {
+    pub id: Option<i32>,
+    pub name: String,
+    pub email: Option<String>,
+    pub office: Option<String>,
+    pub office_hours: Option<String>,
+}
+
+#[derive(Insertable, AsChangeset, Clone)]
+#[diesel(table_name = instructors)]
+pub struct NewInstructorRow {
+    pub name: String,
+    pub email: Option<String>,
+    pub office: Option<String>,
+    pub office_hours: Option<String>,
+}
+
+#[derive(Queryable, Insertable, Clone)]
+#[diesel(table_name = event_instructors)]
+pub struct EventInstructor {
+    pub event_id: i32,
+    pub instructor_id: i32,
+}
+
+#[derive(Queryable, Insertable, Clone)]
+#[diesel(table_name = event_tags)]
+pub struct EventTag {
+    pub event_id: i32,
+    pub tag: String,
+}
+
+#[derive(Queryable)]
+pub struct NoteRow {
+    pub id: Option<i32>,
+    pub event_id: i32,
+    pub body: String,
+}
+
+#[derive(Insertable, Clone)]
+#[diesel(table_name = notes)]
+pub struct NewNoteRow {
+    pub event_id: i32,
+    pub body: String,
+}
+
+#[derive(Queryable)]
+pub struct CourseRow {
+    pub id: Option<i32>,
+    pub code: String,
+    pub title: String,
+    pub ects: f64,
+    pub colour: Option<String>,
+}
+
+#[derive(Insertable, AsChangeset, Clone)]
+#[diesel(table_name = courses)]
+pub struct NewCourseRow {
+    pub code: String,
+    pub title: String,
+    pub ects: f64,
+    pub colour: Option<String>,
+}
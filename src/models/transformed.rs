@@ -1,18 +1,32 @@
 use std::fmt::Display;
+use serde::Serialize;
 use super::*;
 use chrono::{
     self,
     Weekday
 };
+use crate::recurrence::Recurrence;
+use crate::kind::EventKind;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct WeekEvent {
     pub id: Option<i32>,
     pub name: String,
     pub day: Weekday,
     pub starth: chrono::NaiveTime,
     pub endh: chrono::NaiveTime,
-    pub is_lecture: bool,
+    pub kind: EventKind,
+    pub recurrence: Recurrence,
+    ///The term this event belongs to, `None` for events that aren't tied to any term.
+    pub term_id: Option<i32>,
+    ///Set for events that only take place once, on this date, instead of every week.
+    pub date: Option<chrono::NaiveDate>,
+    pub room: Option<Room>,
+    pub instructors: Vec<Instructor>,
+    ///The course this event is a session of.
+    pub course: Option<Course>,
+    pub tags: Vec<String>,
+    pub notes: Vec<Note>,
 }
 
 impl From<Event> for WeekEvent {
@@ -25,20 +39,33 @@ impl From<Event> for WeekEvent {
                     .unwrap(), 
             endh: chrono::NaiveTime::parse_from_str(&event.endh, "%H:%M:%S")
                     .unwrap(), 
-            is_lecture: event.is_lecture != 0 
+            kind: event.kind.parse::<EventKind>().unwrap(),
+            recurrence: event.recurrence.parse::<Recurrence>().unwrap(),
+            term_id: event.term_id,
+            date: event.date.map(|d| d.parse::<chrono::NaiveDate>().unwrap()),
+            room: None,
+            instructors: vec![],
+            course: None,
+            tags: vec![],
+            notes: vec![],
         }
     }
 }
 
-impl Into<Event> for WeekEvent {
-    fn into(self) -> Event {
-        Event { 
-            id: self.id, 
-            name: self.name, 
-            day: self.day.to_string(), 
-            starth: self.starth.to_string(), 
-            endh: self.endh.to_string(), 
-            is_lecture: self.is_lecture as i32 
+impl From<WeekEvent> for Event {
+    fn from(event: WeekEvent) -> Self {
+        Self { 
+            id: event.id, 
+            name: event.name, 
+            day: event.day.to_string(), 
+            starth: event.starth.to_string(), 
+            endh: event.endh.to_string(), 
+            kind: event.kind.to_string(),
+            recurrence: event.recurrence.to_string(),
+            term_id: event.term_id,
+            date: event.date.map(|d| d.to_string()),
+            room_id: event.room.and_then(|r| r.id),
+            course_id: event.course.and_then(|c| c.id),
         }
     }
 }
@@ -51,7 +78,15 @@ pub struct NewWeekEvent {
     pub day: Weekday,
     pub starth: chrono::NaiveTime,
     pub endh: chrono::NaiveTime,
-    pub is_lecture: bool,
+    pub kind: EventKind,
+    pub recurrence: Recurrence,
+    ///Left as `None`, the event joins the active term when stored.
+    pub term_id: Option<i32>,
+    pub date: Option<chrono::NaiveDate>,
+    ///Rooms without an id are looked up, or stored, by building and number.
+    pub room: Option<Room>,
+    ///Courses without an id are looked up by code, they have to be stored already.
+    pub course: Option<Course>,
 }
 impl NewWeekEvent {
     pub fn new(
@@ -59,27 +94,151 @@ impl NewWeekEvent {
         day: Weekday,
         rstarth: &str,
         endh: &str,
-        is_lecture: bool) -> Self {
+        kind: impl Into<EventKind>) -> Self {
             Self {
-                day: day,
-                name: name,
+                day,
+                name,
                 starth: chrono::NaiveTime::parse_from_str(rstarth, "%H:%M:%S")
                         .unwrap(),
                 endh: chrono::NaiveTime::parse_from_str(endh, "%H:%M:%S")
                         .unwrap(),
-                is_lecture: is_lecture,
+                kind: kind.into(),
+                recurrence: Recurrence::Weekly,
+                term_id: None,
+                date: None,
+                room: None,
+                course: None,
             }
     }
+    pub fn with_recurrence(self, recurrence: Recurrence) -> Self {
+        Self { recurrence, ..self }
+    }
+    ///Makes this a one-off event taking place on `date`.
+    pub fn on_date(self, date: chrono::NaiveDate) -> Self {
+        use chrono::Datelike;
+        Self { day: date.weekday(), date: Some(date), ..self }
+    }
+    pub fn in_room(self, room: Room) -> Self {
+        Self { room: Some(room), ..self }
+    }
+    pub fn of_course(self, course: Course) -> Self {
+        Self { course: Some(course), ..self }
+    }
+}
+
+impl NewWeekEvent {
+    ///Turns this event into a `WeekEvent` without storing it.
+    pub fn into_week_event(self, event_id: Option<i32>) -> WeekEvent {
+        WeekEvent {
+            id: event_id,
+            name: self.name,
+            day: self.day,
+            starth: self.starth,
+            endh: self.endh,
+            kind: self.kind,
+            recurrence: self.recurrence,
+            term_id: self.term_id,
+            date: self.date,
+            room: self.room,
+            instructors: vec![],
+            course: self.course,
+            tags: vec![],
+            notes: vec![],
+        }
+    }
 }
 
-impl Into<NewEvent> for NewWeekEvent {
-    fn into(self) -> NewEvent {
-        NewEvent { 
-            name: self.name, 
-            day: self.day.to_string(), 
-            starth: self.starth.to_string(), 
-            endh: self.endh.to_string(), 
-            isLecture: self.is_lecture as i32,
+impl From<WeekEvent> for NewWeekEvent {
+    fn from(event: WeekEvent) -> Self {
+        Self {
+            name: event.name,
+            day: event.day,
+            starth: event.starth,
+            endh: event.endh,
+            kind: event.kind,
+            recurrence: event.recurrence,
+            term_id: event.term_id,
+            date: event.date,
+            room: event.room,
+            course: event.course,
+        }
+    }
+}
+
+impl WeekEvent {
+    ///Whether this event takes place on `date`.
+    pub fn occurs_on(&self, date: chrono::NaiveDate) -> bool {
+        use chrono::Datelike;
+        match self.date {
+            Some(d) => d == date,
+            None => date.weekday() == self.day && self.recurrence.occurs_on(date),
+        }
+    }
+    ///Wraps `text` in the colour of this event's course, or of its kind if the course has none.
+    pub fn paint(&self, text: &str) -> String {
+        match self.course.as_ref().and_then(|c| c.color()) {
+            Some(code) => crate::kind::paint(&code, text),
+            None => self.kind.paint(text),
+        }
+    }
+    ///Hours per week this event takes up on average, one-off events don't count.
+    pub fn weekly_hours(&self) -> f64 {
+        if self.date.is_some() {
+            return 0.0;
+        }
+        (self.endh - self.starth).num_minutes() as f64 / 60.0 * self.recurrence.weekly_share()
+    }
+    ///Returns a copy of this event with the changes from `update` applied.
+    pub fn updated(&self, update: &UpdatedWeekEvent) -> anyhow::Result<Self> {
+        let mut ret = self.clone();
+        if let Some(n) = &update.name {
+            ret.name = n.clone();
+        }
+        if let Some(d) = &update.day {
+            ret.day = d.parse::<Weekday>()
+                .map_err(|_| anyhow::anyhow!("`{}` is not a weekday", d))?;
+        }
+        if let Some(s) = &update.starth {
+            ret.starth = s.parse::<chrono::NaiveTime>()?;
+        }
+        if let Some(e) = &update.endh {
+            ret.endh = e.parse::<chrono::NaiveTime>()?;
+        }
+        if let Some(k) = &update.kind {
+            ret.kind = k.parse::<EventKind>()?;
+        }
+        if let Some(r) = &update.recurrence {
+            ret.recurrence = r.parse::<Recurrence>()?;
+        }
+        if let Some(t) = update.term_id {
+            ret.term_id = t;
+        }
+        if let Some(d) = &update.date {
+            use chrono::Datelike;
+            ret.date = d.as_ref()
+                .map(|d| d.parse::<chrono::NaiveDate>())
+                .transpose()?;
+            if let (Some(d), None) = (ret.date, &update.day) {
+                ret.day = d.weekday();
+            }
+        }
+        Ok(ret)
+    }
+}
+
+impl From<NewWeekEvent> for NewEvent {
+    fn from(event: NewWeekEvent) -> Self {
+        Self { 
+            name: event.name, 
+            day: event.day.to_string(), 
+            starth: event.starth.to_string(), 
+            endh: event.endh.to_string(), 
+            kind: event.kind.to_string(),
+            recurrence: event.recurrence.to_string(),
+            term_id: event.term_id,
+            date: event.date.map(|d| d.to_string()),
+            room_id: event.room.and_then(|r| r.id),
+            course_id: event.course.and_then(|c| c.id),
         }
     }
 }
@@ -87,13 +246,391 @@ impl Into<NewEvent> for NewWeekEvent {
 impl Display for WeekEvent {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         
-        write!(f, "Day: {}\n", self.day)?;
-        write!(f, "Event: {}\n", self.name)?;
-        write!(f, "Is lecture?: {}\n", self.is_lecture)?;
-        write!(f, "Starts at: {}\n", self.starth)?;
-        write!(f, "Ends at: {}\n", self.endh)?;
-        write!(f, "ID: {}\n", self.id.unwrap())
+        writeln!(f, "Day: {}", self.day)?;
+        if let Some(d) = self.date {
+            writeln!(f, "Date: {}", d)?;
+        }
+        writeln!(f, "Event: {}", self.name)?;
+        if let Some(c) = &self.course {
+            writeln!(f, "Course: {}", c)?;
+        }
+        writeln!(f, "Kind: {}", self.kind)?;
+        writeln!(f, "Starts at: {}", self.starth)?;
+        writeln!(f, "Ends at: {}", self.endh)?;
+        if let Some(r) = &self.room {
+            writeln!(f, "Room: {}", r)?;
+        }
+        if !self.instructors.is_empty() {
+            writeln!(f, "Instructors: {}", self.instructors.iter()
+                .map(|i| i.name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", "))?;
+        }
+        if self.recurrence != Recurrence::Weekly && self.date.is_none() {
+            writeln!(f, "Repeats: {}", self.recurrence)?;
+        }
+        if !self.tags.is_empty() {
+            writeln!(f, "Tags: {}", self.tags.join(", "))?;
+        }
+        if !self.notes.is_empty() {
+            writeln!(f, "Notes:")?;
+            for note in &self.notes {
+                write!(f, "{}", note)?;
+            }
+        }
+        writeln!(f, "ID: {}", self.id.unwrap())
+    }
+}
+
+
+
+///Free-form text attached to an event, it may span several lines.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Note {
+    pub id: Option<i32>,
+    pub event_id: i32,
+    pub text: String,
+}
+
+impl Note {
+    pub fn new(event_id: i32, text: impl Into<String>) -> Self {
+        Self { id: None, event_id, text: text.into() }
+    }
+}
+
+impl Display for Note {
+    ///Writes the note's id followed by its lines, indented under it.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "  [{}]", self.id.map(|i| i.to_string()).unwrap_or_default())?;
+        for line in self.text.lines() {
+            writeln!(f, "    {}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<NoteRow> for Note {
+    fn from(note: NoteRow) -> Self {
+        Self { id: note.id, event_id: note.event_id, text: note.body }
+    }
+}
+
+impl From<Note> for NewNoteRow {
+    fn from(note: Note) -> Self {
+        Self { event_id: note.event_id, body: note.text }
+    }
+}
+
+///Where an event takes place.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Room {
+    pub id: Option<i32>,
+    pub building: String,
+    pub number: String,
+    pub notes: Option<String>,
+}
+
+impl Room {
+    pub fn new(building: impl Into<String>, number: impl Into<String>) -> Self {
+        Self { id: None, building: building.into(), number: number.into(), notes: None }
+    }
+    ///Whether this is the same room as `other`, comparing ids when both rooms are stored.
+    pub fn same_as(&self, other: &Room) -> bool {
+        match (self.id, other.id) {
+            (Some(a), Some(b)) => a == b,
+            _ => self.building == other.building && self.number == other.number,
+        }
+    }
+    ///Whether `query` is this room's full name, e.g. `C-13 2.41`, its building or its number,
+    /// ignoring case.
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim();
+        [self.to_string(), self.building.clone(), self.number.clone()]
+            .iter()
+            .any(|n| n.eq_ignore_ascii_case(query))
     }
 }
 
+impl Display for Room {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.building, self.number)
+    }
+}
 
+impl std::str::FromStr for Room {
+    type Err = anyhow::Error;
+    ///Parses a room's full name, the building followed by the room number.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (building, number) = s.trim()
+            .rsplit_once(char::is_whitespace)
+            .ok_or_else(|| anyhow::anyhow!("`{}` is not a room, expected `<building> <room number>`", s))?;
+        Ok(Room::new(building.trim(), number))
+    }
+}
+
+impl From<RoomRow> for Room {
+    fn from(room: RoomRow) -> Self {
+        Self {
+            id: room.id,
+            building: room.building,
+            number: room.number,
+            notes: room.notes,
+        }
+    }
+}
+
+impl From<Room> for NewRoomRow {
+    fn from(room: Room) -> Self {
+        Self {
+            building: room.building,
+            number: room.number,
+            notes: room.notes,
+        }
+    }
+}
+
+///A subject whose lectures, labs and other sessions are linked to it.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Course {
+    pub id: Option<i32>,
+    pub code: String,
+    pub title: String,
+    pub ects: f64,
+    ///`#rrggbb` color the course's sessions are printed in.
+    pub colour: Option<String>,
+}
+
+impl Course {
+    pub fn new(code: impl Into<String>, title: impl Into<String>) -> Self {
+        Self { id: None, code: code.into(), title: title.into(), ects: 0.0, colour: None }
+    }
+    ///ANSI SGR code for the course's colour, if it has a valid one.
+    pub fn color(&self) -> Option<String> {
+        let hex = self.colour.as_ref()?.strip_prefix('#')?;
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        Some(format!("38;2;{};{};{}", channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+impl Display for Course {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.code, self.title)
+    }
+}
+
+impl From<CourseRow> for Course {
+    fn from(course: CourseRow) -> Self {
+        Self {
+            id: course.id,
+            code: course.code,
+            title: course.title,
+            ects: course.ects,
+            colour: course.colour,
+        }
+    }
+}
+
+impl From<Course> for NewCourseRow {
+    fn from(course: Course) -> Self {
+        Self {
+            code: course.code,
+            title: course.title,
+            ects: course.ects,
+            colour: course.colour,
+        }
+    }
+}
+
+///Someone who teaches events.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Instructor {
+    pub id: Option<i32>,
+    pub name: String,
+    pub email: Option<String>,
+    pub office: Option<String>,
+    pub office_hours: Option<String>,
+}
+
+impl Instructor {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { id: None, name: name.into(), email: None, office: None, office_hours: None }
+    }
+}
+
+impl From<InstructorRow> for Instructor {
+    fn from(instructor: InstructorRow) -> Self {
+        Self {
+            id: instructor.id,
+            name: instructor.name,
+            email: instructor.email,
+            office: instructor.office,
+            office_hours: instructor.office_hours,
+        }
+    }
+}
+
+impl From<Instructor> for NewInstructorRow {
+    fn from(instructor: Instructor) -> Self {
+        Self {
+            name: instructor.name,
+            email: instructor.email,
+            office: instructor.office,
+            office_hours: instructor.office_hours,
+        }
+    }
+}
+
+///A semester, events that belong to it only take place between its start and end dates.
+#[derive(Clone, Debug, Serialize)]
+pub struct Term {
+    pub id: i32,
+    pub name: String,
+    pub start: chrono::NaiveDate,
+    pub end: chrono::NaiveDate,
+    pub active: bool,
+}
+
+impl Term {
+    pub fn contains(&self, date: chrono::NaiveDate) -> bool {
+        self.start <= date && date <= self.end
+    }
+}
+
+impl From<TermRow> for Term {
+    fn from(term: TermRow) -> Self {
+        Self {
+            id: term.id.unwrap(),
+            name: term.name,
+            start: term.start_date.parse::<chrono::NaiveDate>().unwrap(),
+            end: term.end_date.parse::<chrono::NaiveDate>().unwrap(),
+            active: term.active != 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NewTerm {
+    pub name: String,
+    pub start: chrono::NaiveDate,
+    pub end: chrono::NaiveDate,
+}
+
+impl From<NewTerm> for NewTermRow {
+    fn from(term: NewTerm) -> Self {
+        Self {
+            name: term.name,
+            start_date: term.start.to_string(),
+            end_date: term.end.to_string(),
+        }
+    }
+}
+
+
+///What happens to a single occurence of an event.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase", tag = "change")]
+pub enum Change {
+    Cancelled,
+    ///The event takes place at another time or in another room that day.
+    Moved {
+        starth: chrono::NaiveTime,
+        endh: chrono::NaiveTime,
+        ///Rooms without an id are looked up, or stored, by building and number.
+        room: Option<Room>,
+    },
+}
+
+///A change to an event on one calendar date, the rest of its occurences stay as they are.
+#[derive(Clone, Debug, Serialize)]
+pub struct EventException {
+    pub id: Option<i32>,
+    pub event_id: i32,
+    pub date: chrono::NaiveDate,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+impl EventException {
+    ///Applies this exception to the occurence of `event` on its date, `None` if it's cancelled.
+    pub fn apply(&self, event: WeekEvent) -> Option<WeekEvent> {
+        match &self.change {
+            Change::Cancelled => None,
+            Change::Moved { starth, endh, room } => Some(WeekEvent {
+                starth: *starth,
+                endh: *endh,
+                room: room.clone().or(event.room.clone()),
+                ..event
+            }),
+        }
+    }
+}
+
+impl From<(ExceptionRow, Option<RoomRow>)> for EventException {
+    fn from((row, room): (ExceptionRow, Option<RoomRow>)) -> Self {
+        let time = |t: Option<String>| t
+            .map(|t| chrono::NaiveTime::parse_from_str(&t, "%H:%M:%S").unwrap());
+        let change = if row.cancelled != 0 {
+            Change::Cancelled
+        } else {
+            Change::Moved {
+                starth: time(row.starth).unwrap(),
+                endh: time(row.endh).unwrap(),
+                room: room.map(|r| r.into()),
+            }
+        };
+        Self {
+            id: row.id,
+            event_id: row.event_id,
+            date: row.date.parse::<chrono::NaiveDate>().unwrap(),
+            change,
+        }
+    }
+}
+
+impl From<EventException> for NewExceptionRow {
+    fn from(exception: EventException) -> Self {
+        let (cancelled, starth, endh, room_id) = match exception.change {
+            Change::Cancelled => (1, None, None, None),
+            Change::Moved { starth, endh, room } =>
+                (0, Some(starth.to_string()), Some(endh.to_string()), room.and_then(|r| r.id)),
+        };
+        Self {
+            event_id: exception.event_id,
+            date: exception.date.to_string(),
+            cancelled,
+            starth,
+            endh,
+            room_id,
+        }
+    }
+}
+
+///A day on which no events take place.
+#[derive(Clone, Debug, Serialize)]
+pub struct Holiday {
+    pub id: Option<i32>,
+    pub date: chrono::NaiveDate,
+    pub name: String,
+}
+
+impl From<HolidayRow> for Holiday {
+    fn from(row: HolidayRow) -> Self {
+        Self {
+            id: row.id,
+            date: row.date.parse::<chrono::NaiveDate>().unwrap(),
+            name: row.name,
+        }
+    }
+}
+
+impl From<Holiday> for NewHolidayRow {
+    fn from(holiday: Holiday) -> Self {
+        Self {
+            date: holiday.date.to_string(),
+            name: holiday.name,
+        }
+    }
+}
@@ -1,13 +1,10 @@
-use chrono::{Weekday, NaiveTime, Datelike};
-use syd::NewWeekEvent;
-use syd::commands::CommandContext;
+use chrono::{Weekday, NaiveTime, NaiveDate};
+use syd::{NewWeekEvent, NewTerm, Recurrence, EventKind, Room, Instructor, Course, Note, EventException, Change};
+use syd::commands::{CommandContext, CommandOutput};
 use syd::models::transformed::{
     WeekEvent,
 };
-use anyhow::{
-    anyhow,
-    Result,
-};
+use anyhow::Result;
 use syd_macros::*;
 
 
@@ -17,110 +14,254 @@ pub struct GeneralModule;
 impl GeneralModule {
     #[command]
     #[command_description("Lists all database entries.")]
-    pub fn all(context: &mut CommandContext) -> Result<()> {
+    pub fn all(context: &mut CommandContext) -> Result<CommandOutput> {
 
         let mut events = context.manager().get_all()?;
         events.sort_by(|a, b| 
             {
                 a.day.num_days_from_monday().cmp(&b.day.num_days_from_monday())
             });
-        events.print();
-        Ok(())
+        Ok(CommandOutput::Events(events))
+    }
+    #[command]
+    #[command_description("Shows the current week as a timetable, entries are marked by their kind, e.g. `*` for lectures.")]
+    pub fn week(context: &mut CommandContext) -> Result<CommandOutput> {
+        let today = context.manager().today()?;
+        Ok(CommandOutput::Week(context.manager().by_week(today)?))
     }
     #[command]
-    #[command_description("Add an entry to the timetable.")]
+    #[command_description("Add an entry to the timetable, `room` is `\"<building> <number>\"`, `--force` allows overlapping other entries.")]
+    #[allow(clippy::too_many_arguments)]
     pub fn add(context: &mut CommandContext, 
         day: Weekday, 
         name: String, 
-        is_lecture: bool,
+        kind: EventKind,
         starth: NaiveTime,
-        endh: NaiveTime) -> Result<()> {
+        endh: NaiveTime,
+        #[default("weekly")] recurrence: Recurrence,
+        room: Option<Room>,
+        #[flag] #[short('f')] force: bool) -> Result<CommandOutput> {
         let new = NewWeekEvent{
-            day: day,
-            name: name,
-            is_lecture: is_lecture,
-            starth: starth,
-            endh: endh
+            day,
+            name,
+            kind,
+            starth,
+            endh,
+            recurrence,
+            term_id: None,
+            date: None,
+            room,
+            course: None,
         };
-        context.manager().add_event(new)?;
-        println!("Event added successfuly!");
-        Ok(())
+        context.manager().add_event(new, force.into())?;
+        Ok(CommandOutput::message("Event added successfuly!"))
     }
     #[command]
-    #[command_description("Deletes an entry with the provided id")]
-    pub fn delete(context: &mut CommandContext, id: i32) -> Result<()> {
-        use std::io;
-        println!("Really? [y/n]");
-        let mut buff = "".to_owned();
-        io::stdin().read_line(&mut buff)?;
-        if buff.trim() == "y" {
-            context.manager().delete_event(id)?;
-            println!("Deleted successfully!");
+    #[command_description("Add a one-off entry taking place only on `date`, `--force` allows overlapping other entries.")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_dated(context: &mut CommandContext, 
+        date: NaiveDate, 
+        name: String, 
+        kind: EventKind,
+        starth: NaiveTime,
+        endh: NaiveTime,
+        room: Option<Room>,
+        #[flag] #[short('f')] force: bool) -> Result<CommandOutput> {
+        use chrono::Datelike;
+        let new = NewWeekEvent{
+            day: date.weekday(),
+            name,
+            kind,
+            starth,
+            endh,
+            recurrence: Recurrence::Weekly,
+            term_id: None,
+            date: Some(date),
+            room,
+            course: None,
+        };
+        context.manager().add_event(new, force.into())?;
+        Ok(CommandOutput::message("Event added successfuly!"))
+    }
+    #[command]
+    #[command_description("Deletes the entries with the provided ids, e.g. `delete 3 7 12`, asks for confirmation unless `--yes` is given.")]
+    pub fn delete(context: &mut CommandContext, mut ids: Vec<i32>, #[flag] #[short('y')] yes: bool) -> Result<CommandOutput> {
+        let entries = |count: usize| match count {
+            1 => "1 entry".to_owned(),
+            n => format!("{} entries", n),
+        };
+        if !yes {
+            context.manager().check_events(&ids)?;
+            ids.sort();
+            ids.dedup();
+            return Ok(CommandOutput::Confirm {
+                prompt: format!("Really delete {}?", entries(ids.len())),
+                command: format!("delete {} --yes", ids.iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" ")),
+            });
         }
-        Ok(())
+        let count = context.manager().delete_events(&ids)?;
+        Ok(CommandOutput::message(format!("Deleted {}.", entries(count))))
     }
     #[command]
-    #[command_description("Change an entry with given id")]
+    #[command_description("Change an entry with given id, e.g. `change 5 --endh 12:00`, fields that aren't given stay as they are, `--force` allows overlapping other entries.")]
+    #[allow(clippy::too_many_arguments)]
     pub fn change(context: &mut CommandContext, 
         id: i32,
-        new_day: Option<Weekday>, 
-        new_name: Option<String>,
-        new_is_lecture: Option<bool>,
-        new_statrh: Option<NaiveTime>,
-        new_endh: Option<NaiveTime>
+        #[option] #[short('d')] day: Option<Weekday>, 
+        #[option] #[short('n')] name: Option<String>,
+        #[option] #[short('k')] kind: Option<EventKind>,
+        #[option] #[short('s')] starth: Option<NaiveTime>,
+        #[option] #[short('e')] endh: Option<NaiveTime>,
+        #[option] #[short('r')] recurrence: Option<Recurrence>,
+        #[option] room: Option<Room>,
+        #[flag] #[short('f')] force: bool
         ) 
-        -> Result<()>
+        -> Result<CommandOutput>
     {
         use syd::models::UpdatedWeekEvent;
         context.manager().change_event(UpdatedWeekEvent 
             { 
-                id: id, 
-                name: new_name, 
-                day: match new_day {
-                    Some(d) => Some(d.to_string()),
-                    None => None,
-                }, 
-                starth: match new_statrh {
-                    Some(s) => Some(s.to_string()),
-                    None => None,
-                }, 
-                endh: match new_endh {
-                    Some(e) => Some(e.to_string()),
-                    None => None,
-                }, 
-                isLecture: match new_is_lecture {
-                    Some(l) => Some(l as i32),
-                    None => None
-                } 
-            })?;
-        println!("Event changed!");
-        println!("{}", context.manager().get_event(id)?);
-        Ok(())
+                id, 
+                name, 
+                day: day.map(|d| d.to_string()), 
+                starth: starth.map(|s| s.to_string()), 
+                endh: endh.map(|e| e.to_string()), 
+                recurrence: recurrence.map(|r| r.to_string()),
+                kind: kind.map(|k| k.to_string()),
+                room,
+                ..Default::default()
+            }, force.into())?;
+        let event = context.manager().get_event(id)?;
+        Ok(CommandOutput::Many(vec![
+            CommandOutput::message("Event changed!"),
+            CommandOutput::Event(Box::new(event)),
+        ]))
+    }
+    #[command]
+    #[command_description("Lists all pairs of overlapping entries.")]
+    pub fn conflicts(context: &mut CommandContext) -> Result<CommandOutput> {
+        use syd::conflict::summary;
+        let conflicts = context.manager().find_conflicts()?;
+        if conflicts.is_empty() {
+            return Ok(CommandOutput::message("No conflicts found!"));
+        }
+        Ok(CommandOutput::Table {
+            headers: vec!["day".into(), "event".into(), "overlaps with".into()],
+            rows: conflicts.iter()
+                .map(|(a, b)| vec![a.day.to_string(), summary(a), summary(b)])
+                .collect(),
+        })
+    }
+    #[command]
+    #[command_description("Finds free slots of at least `min_minutes` between `from` and `to`, in the whole week or only on `--day`, e.g. `free 60 --day Mon`.")]
+    pub fn free(context: &mut CommandContext,
+        min_minutes: u32,
+        #[default("08:00")] from: NaiveTime,
+        #[default("20:00")] to: NaiveTime,
+        #[option] #[short('d')] day: Option<Weekday>) -> Result<CommandOutput> {
+        let slots = context.manager()
+            .free_slots(day, chrono::Duration::minutes(min_minutes as i64), (from, to))?;
+        if slots.is_empty() {
+            return Ok(CommandOutput::message("No free slots found!"));
+        }
+        Ok(CommandOutput::Table {
+            headers: vec!["day".into(), "from".into(), "to".into(), "length".into()],
+            rows: slots.iter()
+                .map(|s| vec![
+                    s.day.to_string(),
+                    s.start.format("%H:%M").to_string(),
+                    s.end.format("%H:%M").to_string(),
+                    format!("{}h {:02}m", s.duration().num_hours(), s.duration().num_minutes() % 60),
+                ])
+                .collect(),
+        })
     }
     #[command]
     #[command_description("Get events of today")]
-    pub fn today(context: &mut CommandContext) -> Result<()> {
-        let now = chrono::Utc::now();
-        let day = now.weekday();
-        context.manager().by_day(day)?.print();
-        Ok(())
-    }
-    #[command]
-    #[command_description("Gets the closest x amount of events today")]
-    pub fn near(context: &mut CommandContext, amount: u32) -> Result<()> {
-        use chrono::Utc;
-        let events = context.manager()
-            .by_day(Utc::now().weekday())?;
-        events.into_iter()
-            .filter(|e| {
-                e.starth > Utc::now().time()
-            })
-            .take(amount as usize)
-            .collect::<Vec<WeekEvent>>()
-            .print();
-        Ok(())
+    pub fn today(context: &mut CommandContext) -> Result<CommandOutput> {
+        let today = context.manager().today()?;
+        Ok(CommandOutput::Events(context.manager().by_date(today)?))
+    }
+    #[command]
+    #[command_description("Lists the next x entries with how long until they start, looking into the following days too.")]
+    pub fn near(context: &mut CommandContext, #[default(3)] amount: u32) -> Result<CommandOutput> {
+        let now = context.manager().now()?.naive_local();
+        let upcoming = context.manager().upcoming(now, amount as usize)?;
+        if upcoming.is_empty() {
+            return Ok(CommandOutput::message("Nothing coming up."));
+        }
+        Ok(CommandOutput::Table {
+            headers: vec!["date".into(), "time".into(), "event".into(), "kind".into(), "room".into(), "starts in".into()],
+            rows: upcoming.into_iter()
+                .map(|(on, e)| vec![
+                    format!("{} {}", on.format("%a"), on),
+                    format!("{}-{}", e.starth.format("%H:%M"), e.endh.format("%H:%M")),
+                    e.name.clone(),
+                    e.kind.to_string(),
+                    e.room.as_ref().map(|r| r.to_string()).unwrap_or_default(),
+                    countdown(on.and_time(e.starth) - now),
+                ])
+                .collect(),
+        })
     }
+    #[command]
+    #[command_description("Shows the entries taking place right now and how long until they end.")]
+    pub fn now(context: &mut CommandContext) -> Result<CommandOutput> {
+        let now = context.manager().now()?.naive_local();
+        let events = context.manager().happening_at(now)?;
+        if events.is_empty() {
+            return Ok(CommandOutput::message("Nothing is happening right now."));
+        }
+        Ok(CommandOutput::Table {
+            headers: vec!["event".into(), "kind".into(), "room".into(), "ends at".into(), "time left".into()],
+            rows: events.into_iter()
+                .map(|e| vec![
+                    e.name.clone(),
+                    e.kind.to_string(),
+                    e.room.as_ref().map(|r| r.to_string()).unwrap_or_default(),
+                    e.endh.format("%H:%M").to_string(),
+                    countdown(now.date().and_time(e.endh) - now),
+                ])
+                .collect(),
+        })
+    }
+    #[command]
+    #[command_description("Shows the next entry, even if it's on a later day, and how long until it starts.")]
+    pub fn next(context: &mut CommandContext) -> Result<CommandOutput> {
+        let now = context.manager().now()?.naive_local();
+        let Some((on, event)) = context.manager().upcoming(now, 1)?.pop() else {
+            return Ok(CommandOutput::message("Nothing coming up."));
+        };
+        let starts = on.and_time(event.starth);
+        Ok(CommandOutput::Many(vec![
+            CommandOutput::message(format!("Starts in {}, on {} {} at {}.",
+                countdown(starts - now), on.format("%A"), on, event.starth.format("%H:%M"))),
+            CommandOutput::Event(Box::new(event)),
+        ]))
+    }
+    #[command]
+    #[command_description("Sets the timezone of the timetable, e.g. `Europe/Warsaw`, or shows it when left out.")]
+    pub fn timezone(context: &mut CommandContext, tz: Option<chrono_tz::Tz>) -> Result<CommandOutput> {
+        if let Some(tz) = tz {
+            context.manager().set_timezone(tz)?;
+        }
+        let now = context.manager().now()?;
+        Ok(CommandOutput::message(format!("Timezone: {}, it's {} there.",
+            now.timezone(), now.format("%A %Y-%m-%d %H:%M"))))
+    }
+}
 
+///Formats a duration as e.g. `1d 2h 05m`, leaving out days when there are none.
+fn countdown(duration: chrono::Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        return format!("{}d {}h {:02}m", days, hours, minutes);
+    }
+    format!("{}h {:02}m", hours, minutes)
 }
 
 pub struct GetModule;
@@ -130,65 +271,523 @@ pub struct GetModule;
 impl GetModule {
     #[command]
     #[command_description("Looks for an entry with provided id.")]
-    pub fn id(context: &mut CommandContext, id: i32) -> Result<()> {
-        let event = context.manager().get_event(id)?;
-        println!("{}", event);
-        Ok(())
+    pub fn id(context: &mut CommandContext, id: i32) -> Result<CommandOutput> {
+        Ok(CommandOutput::Event(Box::new(context.manager().get_event(id)?)))
+    }
+    #[command]
+    #[command_description("Gets entries by day in the current week, e.g. `get day Mon Wed Fri`.")]
+    pub fn day(context: &mut CommandContext, days: Vec<Weekday>) -> Result<CommandOutput> {
+        let week = syd::recurrence::week_start(context.manager().today()?);
+        let mut days = days.into_iter()
+            .map(|d| week + chrono::Duration::days(d.num_days_from_monday() as i64))
+            .collect::<Vec<NaiveDate>>();
+        days.sort();
+        days.dedup();
+        let mut ev = vec![];
+        for date in days {
+            ev.extend(context.manager().by_date(date)?);
+        }
+        Ok(CommandOutput::Events(ev))
+    }
+    #[command]
+    #[command_description("Gets weekly and one-off entries taking place on `date`.")]
+    pub fn date(context: &mut CommandContext, date: NaiveDate) -> Result<CommandOutput> {
+        Ok(CommandOutput::Events(context.manager().by_date(date)?))
+    }
+    #[command]
+    #[command_description("Gets entries taking place in a room, given its full name, building or number.")]
+    pub fn room(context: &mut CommandContext, room: String) -> Result<CommandOutput> {
+        Ok(CommandOutput::Events(context.manager().by_room(&room)?))
+    }
+    #[command]
+    #[command_description("Gets entries taught by instructors whose name contains `name`.")]
+    pub fn instructor(context: &mut CommandContext, name: String) -> Result<CommandOutput> {
+        Ok(CommandOutput::Events(context.manager().by_instructor(&name)?))
     }
     #[command]
-    #[command_description("Gets entries by day.")]
-    pub fn day(context: &mut CommandContext, day: Weekday) -> Result<()> {
-        let mut ev = context.manager().by_day(day)?;
-        ev.sort_by(|a, b| a.starth.cmp(&b.starth));
-        ev.print();
-        Ok(())
+    #[command_description("Gets entries tagged with `tag`, ignoring case.")]
+    pub fn tag(context: &mut CommandContext, tag: String) -> Result<CommandOutput> {
+        Ok(CommandOutput::Events(context.manager().by_tag(&tag)?))
     }
     #[command]
     #[command_description("Gets entries by start hour")]
-    pub fn starth(context: &mut CommandContext, starth: NaiveTime) -> Result<()> {
-        context.manager().by_starth(starth)?.print();
-        Ok(())
+    pub fn starth(context: &mut CommandContext, starth: NaiveTime) -> Result<CommandOutput> {
+        Ok(CommandOutput::Events(context.manager().by_starth(starth)?))
     }
     #[command]
     #[command_description("Gets entries by end hour.")]
-    pub fn endh(context: &mut CommandContext, endh: NaiveTime) -> Result<()> {
-        context.manager().by_endh(endh)?.print();
-        Ok(())
+    pub fn endh(context: &mut CommandContext, endh: NaiveTime) -> Result<CommandOutput> {
+        Ok(CommandOutput::Events(context.manager().by_endh(endh)?))
     }
     #[command]
-    #[command_description("Gets entries based on wether they are lectures.")]
-    pub fn is_lecture(context: &mut CommandContext, is_lecture: bool) -> Result<()> {
-        context.manager().by_is_lecture(is_lecture)?.print();
-        Ok(())
+    #[command_description("Gets entries of a kind: `lecture`, `lab`, `seminar`, `exercise`, `exam` or `other`.")]
+    pub fn kind(context: &mut CommandContext, kind: EventKind) -> Result<CommandOutput> {
+        Ok(CommandOutput::Events(context.manager().by_kind(kind)?))
     }
 }
 
-pub struct TestModule;
+pub struct CsvModule;
 
 #[command_module]
-impl TestModule {
+#[command_group("io csv")]
+impl CsvModule {
+    #[command]
+    #[command_description("Exports the timetable to a CSV file.")]
+    pub fn export(context: &mut CommandContext, path: String) -> Result<CommandOutput> {
+        context.manager().export_csv(std::fs::File::create(&path)?)?;
+        Ok(CommandOutput::message(format!("Timetable exported to {}", path)))
+    }
+    #[command]
+    #[command_description("Imports entries from a CSV file with a header row.")]
+    pub fn import(context: &mut CommandContext, path: String) -> Result<CommandOutput> {
+        let count = context.manager().import_csv(std::fs::File::open(&path)?)?;
+        Ok(CommandOutput::message(format!("Imported {} event(s).", count)))
+    }
+}
+
+pub struct IcsModule;
 
+#[command_module]
+#[command_group("io ics")]
+impl IcsModule {
     #[command]
-    #[command_description("test command")]
-    pub fn test(_context: &mut CommandContext, val: i32) -> Result<()> {
-        println!("{} * 2 = {}!", val, val * 2);
-        Ok(())
+    #[command_description("Exports the timetable to an .ics file, with events repeating weekly from the semester start date.")]
+    pub fn export(context: &mut CommandContext, path: String, semester_start: NaiveDate) -> Result<CommandOutput> {
+        let calendar = context.manager().export_ics(semester_start)?;
+        std::fs::write(&path, calendar)?;
+        Ok(CommandOutput::message(format!("Timetable exported to {}", path)))
+    }
+    #[command]
+    #[command_description("Imports weekly recurring events from an .ics file.")]
+    pub fn import(context: &mut CommandContext, path: String) -> Result<CommandOutput> {
+        let calendar = std::fs::read_to_string(&path)?;
+        let imported = context.manager().import_ics(&calendar)?;
+        let mut output = vec![
+            CommandOutput::message(format!("Imported {} event(s).", imported.events.len()))
+        ];
+        for skipped in &imported.skipped {
+            output.push(CommandOutput::message(
+                format!("Skipped `{}`: {}", skipped.summary, skipped.reason)));
+        }
+        Ok(CommandOutput::Many(output))
+    }
+}
+
+pub struct TermModule;
+
+#[command_module]
+#[command_group("term")]
+impl TermModule {
+    #[command]
+    #[command_description("Adds a term (semester) lasting from `start` to `end`.")]
+    pub fn add(context: &mut CommandContext, name: String, start: NaiveDate, end: NaiveDate) -> Result<CommandOutput> {
+        context.manager().add_term(NewTerm { name, start, end })?;
+        Ok(CommandOutput::message("Term added successfuly!"))
+    }
+    #[command]
+    #[command_description("Lists all terms.")]
+    pub fn list(context: &mut CommandContext) -> Result<CommandOutput> {
+        let terms = context.manager().get_terms()?;
+        if terms.is_empty() {
+            return Ok(CommandOutput::message("No terms found!"));
+        }
+        Ok(CommandOutput::Table {
+            headers: vec!["id".into(), "name".into(), "start".into(), "end".into(), "active".into()],
+            rows: terms.iter()
+                .map(|t| vec![
+                    t.id.to_string(),
+                    t.name.clone(),
+                    t.start.to_string(),
+                    t.end.to_string(),
+                    if t.active { "*".into() } else { String::new() },
+                ])
+                .collect(),
+        })
+    }
+    #[command]
+    #[command_group("set")]
+    #[command_description("Makes the term with given id the active one, leave it out to stop scoping entries to a term.")]
+    pub fn active(context: &mut CommandContext, id: Option<i32>) -> Result<CommandOutput> {
+        context.manager().set_active_term(id)?;
+        match id {
+            Some(id) => Ok(CommandOutput::message(format!("Term {} is now active.", id))),
+            None => Ok(CommandOutput::message("No term is active.")),
+        }
+    }
+    #[command]
+    #[command_description("Moves entries that don't belong to any term into the term with given id.")]
+    pub fn adopt(context: &mut CommandContext, id: i32) -> Result<CommandOutput> {
+        let count = context.manager().adopt_events(id)?;
+        Ok(CommandOutput::message(format!("Moved {} event(s) into term {}.", count, id)))
+    }
+}
+
+pub struct RoomModule;
+
+#[command_module]
+#[command_group("room")]
+impl RoomModule {
+    #[command]
+    #[command_description("Adds a room, or changes the notes of an existing one.")]
+    pub fn add(context: &mut CommandContext, building: String, number: String, notes: Option<String>) -> Result<CommandOutput> {
+        let room = context.manager().add_room(Room { id: None, building, number, notes })?;
+        Ok(CommandOutput::message(format!("Room {} saved with id {}.", room, room.id.unwrap_or_default())))
+    }
+    #[command]
+    #[command_description("Lists all rooms.")]
+    pub fn list(context: &mut CommandContext) -> Result<CommandOutput> {
+        let rooms = context.manager().get_rooms()?;
+        if rooms.is_empty() {
+            return Ok(CommandOutput::message("No rooms found!"));
+        }
+        Ok(CommandOutput::Table {
+            headers: vec!["id".into(), "building".into(), "number".into(), "notes".into()],
+            rows: rooms.into_iter()
+                .map(|r| vec![
+                    r.id.map(|i| i.to_string()).unwrap_or_default(),
+                    r.building,
+                    r.number,
+                    r.notes.unwrap_or_default(),
+                ])
+                .collect(),
+        })
+    }
+    #[command]
+    #[command_description("Deletes a room, its entries are kept without a room.")]
+    pub fn delete(context: &mut CommandContext, id: i32) -> Result<CommandOutput> {
+        context.manager().delete_room(id)?;
+        Ok(CommandOutput::message("Room deleted!"))
+    }
+}
+
+pub struct InstructorModule;
+
+#[command_module]
+#[command_group("instructor")]
+impl InstructorModule {
+    #[command]
+    #[command_description("Adds an instructor, or updates the contact info of an existing one, fields left out or given as `!` stay as they are.")]
+    pub fn add(context: &mut CommandContext,
+        name: String,
+        email: Option<String>,
+        office: Option<String>,
+        office_hours: Option<String>) -> Result<CommandOutput> {
+        let instructor = context.manager().add_instructor(Instructor { id: None, name, email, office, office_hours })?;
+        Ok(CommandOutput::message(format!("Instructor {} saved!", instructor.name)))
+    }
+    #[command]
+    #[command_description("Lists all instructors with their contact info.")]
+    pub fn list(context: &mut CommandContext) -> Result<CommandOutput> {
+        let instructors = context.manager().get_instructors()?;
+        if instructors.is_empty() {
+            return Ok(CommandOutput::message("No instructors found!"));
+        }
+        Ok(CommandOutput::Table {
+            headers: vec!["name".into(), "email".into(), "office".into(), "office hours".into()],
+            rows: instructors.into_iter()
+                .map(|i| vec![
+                    i.name,
+                    i.email.unwrap_or_default(),
+                    i.office.unwrap_or_default(),
+                    i.office_hours.unwrap_or_default(),
+                ])
+                .collect(),
+        })
+    }
+    #[command]
+    #[command_description("Makes the instructor one of the people teaching the entry with given id.")]
+    pub fn assign(context: &mut CommandContext, id: i32, name: String) -> Result<CommandOutput> {
+        let instructor = context.manager().get_instructor(&name)?;
+        context.manager().get_event(id)?;
+        context.manager().assign_instructor(id, instructor.id.unwrap_or_default())?;
+        Ok(CommandOutput::message(format!("{} now teaches entry {}.", instructor.name, id)))
+    }
+    #[command]
+    #[command_description("Stops the instructor from teaching the entry with given id.")]
+    pub fn unassign(context: &mut CommandContext, id: i32, name: String) -> Result<CommandOutput> {
+        let instructor = context.manager().get_instructor(&name)?;
+        if context.manager().unassign_instructor(id, instructor.id.unwrap_or_default())? {
+            return Ok(CommandOutput::message(format!("{} no longer teaches entry {}.", instructor.name, id)));
+        }
+        Ok(CommandOutput::message(format!("{} doesn't teach entry {}.", instructor.name, id)))
+    }
+    #[command]
+    #[command_description("Deletes an instructor.")]
+    pub fn delete(context: &mut CommandContext, name: String) -> Result<CommandOutput> {
+        let instructor = context.manager().get_instructor(&name)?;
+        context.manager().delete_instructor(instructor.id.unwrap_or_default())?;
+        Ok(CommandOutput::message("Instructor deleted!"))
+    }
+    #[command]
+    #[command_description("Shows the weekly teaching load of an instructor, alternating entries count proportionally.")]
+    pub fn load(context: &mut CommandContext, name: String) -> Result<CommandOutput> {
+        let instructor = context.manager().get_instructor(&name)?;
+        let mut events = context.manager().get_all()?
+            .into_iter()
+            .filter(|e| e.date.is_none() && e.instructors.iter().any(|i| i.id == instructor.id))
+            .collect::<Vec<WeekEvent>>();
+        events.sort_by_key(|e| (e.day.num_days_from_monday(), e.starth));
+        let total = events.iter().map(|e| e.weekly_hours()).sum::<f64>();
+        Ok(CommandOutput::Many(vec![
+            CommandOutput::Table {
+                headers: vec!["day".into(), "time".into(), "event".into(), "kind".into(), "hours/week".into()],
+                rows: events.iter()
+                    .map(|e| vec![
+                        e.day.to_string(),
+                        format!("{}-{}", e.starth.format("%H:%M"), e.endh.format("%H:%M")),
+                        e.name.clone(),
+                        e.kind.to_string(),
+                        format!("{:.2}", e.weekly_hours()),
+                    ])
+                    .collect(),
+            },
+            CommandOutput::message(format!("{} teaches {:.2} hours a week.", instructor.name, total)),
+        ]))
     }
 }
 
-trait EventsExt {
-    fn print(&self);
+pub struct CourseModule;
+
+#[command_module]
+#[command_group("course")]
+impl CourseModule {
+    #[command]
+    #[command_description("Adds a course, or updates the one with the same code, `colour` is `#rrggbb`.")]
+    pub fn add(context: &mut CommandContext,
+        code: String,
+        title: String,
+        ects: f64,
+        colour: Option<String>) -> Result<CommandOutput> {
+        let course = context.manager().add_course(Course { id: None, code, title, ects, colour })?;
+        Ok(CommandOutput::message(format!("Course {} saved!", course)))
+    }
+    #[command]
+    #[command_description("Lists all courses with their number of sessions and weekly hours.")]
+    pub fn list(context: &mut CommandContext) -> Result<CommandOutput> {
+        let courses = context.manager().get_courses()?;
+        if courses.is_empty() {
+            return Ok(CommandOutput::message("No courses found!"));
+        }
+        let events = context.manager().get_all()?;
+        Ok(CommandOutput::Table {
+            headers: vec!["code".into(), "title".into(), "ects".into(), "sessions".into(), "hours/week".into()],
+            rows: courses.into_iter()
+                .map(|c| {
+                    let sessions = events.iter()
+                        .filter(|e| e.course.as_ref().map(|ec| ec.id) == Some(c.id))
+                        .collect::<Vec<&WeekEvent>>();
+                    vec![
+                        c.code,
+                        c.title,
+                        c.ects.to_string(),
+                        sessions.len().to_string(),
+                        format!("{:.2}", sessions.iter().map(|e| e.weekly_hours()).sum::<f64>()),
+                    ]
+                })
+                .collect(),
+        })
+    }
+    #[command]
+    #[command_description("Shows a course with all of its sessions and their total weekly hours.")]
+    pub fn show(context: &mut CommandContext, code: String) -> Result<CommandOutput> {
+        let course = context.manager().get_course(&code)?;
+        let events = context.manager().by_course(&code)?;
+        let total = events.iter().map(|e| e.weekly_hours()).sum::<f64>();
+        Ok(CommandOutput::Many(vec![
+            CommandOutput::message(format!("{} ({} ECTS)", course, course.ects)),
+            CommandOutput::Events(events),
+            CommandOutput::message(format!("{:.2} hours a week.", total)),
+        ]))
+    }
+    #[command]
+    #[command_description("Makes the entry with given id a session of the course.")]
+    pub fn assign(context: &mut CommandContext, id: i32, code: String) -> Result<CommandOutput> {
+        use syd::models::UpdatedWeekEvent;
+        let course = context.manager().get_course(&code)?;
+        context.manager().change_event(UpdatedWeekEvent {
+            id,
+            course_id: Some(course.id),
+            ..Default::default()
+        }, syd::ConflictPolicy::Allow)?;
+        Ok(CommandOutput::message(format!("Entry {} is now a session of {}.", id, course.code)))
+    }
+    #[command]
+    #[command_description("Makes every entry called `name` a session of the course.")]
+    pub fn attach(context: &mut CommandContext, code: String, name: String) -> Result<CommandOutput> {
+        let course = context.manager().get_course(&code)?;
+        let linked = context.manager().link_course(course.id.unwrap_or_default(), &name)?;
+        Ok(CommandOutput::message(format!("{} entries are now sessions of {}.", linked, course.code)))
+    }
+    #[command]
+    #[command_description("Deletes a course, its sessions are kept.")]
+    pub fn delete(context: &mut CommandContext, code: String) -> Result<CommandOutput> {
+        let course = context.manager().get_course(&code)?;
+        context.manager().delete_course(course.id.unwrap_or_default())?;
+        Ok(CommandOutput::message("Course deleted!"))
+    }
 }
 
-impl EventsExt for Vec<WeekEvent> {
-    fn print(&self) {
-        if self.is_empty() {
-            println!("No events found!");
-        } else {
-            for e in self {
-                println!("{}", e);
-            }
+pub struct TagModule;
+
+#[command_module]
+#[command_group("tag")]
+impl TagModule {
+    #[command]
+    #[command_description("Tags the entry with given id, e.g. `tag add 3 \"bring laptop\"`.")]
+    pub fn add(context: &mut CommandContext, id: i32, tag: String) -> Result<CommandOutput> {
+        context.manager().get_event(id)?;
+        if context.manager().add_tag(id, &tag)? {
+            return Ok(CommandOutput::message(format!("Entry {} tagged with `{}`.", id, tag.trim())));
+        }
+        Ok(CommandOutput::message(format!("Entry {} is already tagged with `{}`.", id, tag.trim())))
+    }
+    #[command]
+    #[command_description("Removes a tag from the entry with given id.")]
+    pub fn remove(context: &mut CommandContext, id: i32, tag: String) -> Result<CommandOutput> {
+        if context.manager().remove_tag(id, &tag)? {
+            return Ok(CommandOutput::message(format!("Removed `{}` from entry {}.", tag.trim(), id)));
         }
+        Ok(CommandOutput::message(format!("Entry {} isn't tagged with `{}`.", id, tag.trim())))
     }
 }
 
+pub struct NoteModule;
+
+#[command_module]
+#[command_group("note")]
+impl NoteModule {
+    #[command]
+    #[command_description("Attaches a note to the entry with given id, e.g. `note add 3 bring a calculator`, `\\n` or a line break in quotes starts a new line.")]
+    pub fn add(context: &mut CommandContext, id: i32, text: Vec<String>) -> Result<CommandOutput> {
+        context.manager().get_event(id)?;
+        let text = text.join(" ").replace("\\n", "\n");
+        let note = context.manager().add_note(Note::new(id, text))?;
+        Ok(CommandOutput::message(format!("Note {} added to entry {}.", note.id.unwrap_or_default(), id)))
+    }
+    #[command]
+    #[command_description("Removes the note with given id, note ids are shown next to the notes of an entry.")]
+    pub fn remove(context: &mut CommandContext, note_id: i32) -> Result<CommandOutput> {
+        if context.manager().remove_note(note_id)? {
+            return Ok(CommandOutput::message("Note removed!"));
+        }
+        Ok(CommandOutput::message(format!("No note with id {}.", note_id)))
+    }
+}
+
+pub struct ExceptionModule;
+
+#[command_module]
+#[command_group("exception")]
+impl ExceptionModule {
+    #[command]
+    #[command_description("Cancels the entry with given id on `date` only.")]
+    pub fn cancel(context: &mut CommandContext, id: i32, date: NaiveDate) -> Result<CommandOutput> {
+        context.manager().add_exception(EventException {
+            id: None,
+            event_id: id,
+            date,
+            change: Change::Cancelled,
+        })?;
+        Ok(CommandOutput::message(format!("Event cancelled on {}.", date)))
+    }
+    #[command]
+    #[command_description("Moves the entry with given id to another time or room on `date` only, hours left out or given as `!` stay as they are.")]
+    pub fn reschedule(context: &mut CommandContext,
+        id: i32,
+        date: NaiveDate,
+        starth: Option<NaiveTime>,
+        endh: Option<NaiveTime>,
+        room: Option<Room>) -> Result<CommandOutput> {
+        let event = context.manager().get_event(id)?;
+        context.manager().add_exception(EventException {
+            id: None,
+            event_id: id,
+            date,
+            change: Change::Moved {
+                starth: starth.unwrap_or(event.starth),
+                endh: endh.unwrap_or(event.endh),
+                room,
+            },
+        })?;
+        Ok(CommandOutput::message(format!("Event moved on {}.", date)))
+    }
+    #[command]
+    #[command_description("Undoes the cancellation or move of the entry with given id on `date`.")]
+    pub fn restore(context: &mut CommandContext, id: i32, date: NaiveDate) -> Result<CommandOutput> {
+        if context.manager().remove_exception(id, date)? {
+            return Ok(CommandOutput::message(format!("Event restored on {}.", date)));
+        }
+        Ok(CommandOutput::message(format!("Event had no changes on {}.", date)))
+    }
+    #[command]
+    #[command_description("Lists all cancelled and moved entries.")]
+    pub fn list(context: &mut CommandContext) -> Result<CommandOutput> {
+        let exceptions = context.manager().get_exceptions()?;
+        if exceptions.is_empty() {
+            return Ok(CommandOutput::message("No exceptions found!"));
+        }
+        Ok(CommandOutput::Table {
+            headers: vec!["date".into(), "event".into(), "change".into()],
+            rows: exceptions.iter()
+                .map(|x| vec![
+                    x.date.to_string(),
+                    x.event_id.to_string(),
+                    match &x.change {
+                        Change::Cancelled => "cancelled".to_owned(),
+                        Change::Moved { starth, endh, room } => format!("moved to {}-{}{}",
+                            starth.format("%H:%M"),
+                            endh.format("%H:%M"),
+                            room.as_ref().map(|r| format!(" in {}", r)).unwrap_or_default()),
+                    },
+                ])
+                .collect(),
+        })
+    }
+}
+
+pub struct HolidayModule;
+
+#[command_module]
+#[command_group("holiday")]
+impl HolidayModule {
+    #[command]
+    #[command_description("Adds a holiday on `date`, or on every day from `date` to `until` when it's given.")]
+    pub fn add(context: &mut CommandContext, date: NaiveDate, name: String, until: Option<NaiveDate>) -> Result<CommandOutput> {
+        let count = context.manager().add_holidays(date..=until.unwrap_or(date), &name)?;
+        Ok(CommandOutput::message(format!("Added {} day(s) off.", count)))
+    }
+    #[command]
+    #[command_description("Removes the holiday on `date`.")]
+    pub fn remove(context: &mut CommandContext, date: NaiveDate) -> Result<CommandOutput> {
+        if context.manager().remove_holiday(date)? {
+            return Ok(CommandOutput::message("Holiday removed!"));
+        }
+        Ok(CommandOutput::message(format!("No holiday on {}.", date)))
+    }
+    #[command]
+    #[command_description("Lists all holidays.")]
+    pub fn list(context: &mut CommandContext) -> Result<CommandOutput> {
+        let holidays = context.manager().get_holidays()?;
+        if holidays.is_empty() {
+            return Ok(CommandOutput::message("No holidays found!"));
+        }
+        Ok(CommandOutput::Table {
+            headers: vec!["date".into(), "name".into()],
+            rows: holidays.iter()
+                .map(|h| vec![h.date.to_string(), h.name.clone()])
+                .collect(),
+        })
+    }
+}
+
+pub struct TestModule;
+
+#[command_module]
+impl TestModule {
+
+    #[command]
+    #[command_description("test command")]
+    pub fn test(_context: &mut CommandContext, val: i32) -> Result<CommandOutput> {
+        Ok(CommandOutput::message(format!("{} * 2 = {}!", val, val * 2)))
+    }
+}
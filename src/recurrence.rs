@@ -0,0 +1,111 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use chrono::{Datelike, NaiveDate};
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Serializer};
+
+///How often a weekly event takes place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Recurrence {
+    ///Every week.
+    #[default]
+    Weekly,
+    ///Only in odd ISO weeks.
+    Odd,
+    ///Only in even ISO weeks.
+    Even,
+    ///Every `weeks` weeks, counting from the week that contains `anchor`.
+    Every {
+        weeks: u32,
+        anchor: NaiveDate,
+    },
+}
+
+impl Recurrence {
+    ///Whether an event with this recurrence takes place in the week containing `date`.
+    //`is_multiple_of` needs Rust 1.87, which is newer than the crate asks for
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn occurs_on(&self, date: NaiveDate) -> bool {
+        match self {
+            Recurrence::Weekly => true,
+            Recurrence::Odd => date.iso_week().week() % 2 == 1,
+            Recurrence::Even => date.iso_week().week() % 2 == 0,
+            Recurrence::Every { weeks, anchor } => {
+                let distance = (week_start(date) - week_start(*anchor)).num_weeks();
+                distance.rem_euclid(*weeks as i64) == 0
+            },
+        }
+    }
+    ///Fraction of weeks in which an event with this recurrence takes place.
+    pub fn weekly_share(&self) -> f64 {
+        match self {
+            Recurrence::Weekly => 1.0,
+            Recurrence::Odd | Recurrence::Even => 0.5,
+            Recurrence::Every { weeks, .. } => 1.0 / *weeks as f64,
+        }
+    }
+    ///Whether two events with these recurrences can ever take place in the same week.
+    ///
+    /// Errs on the side of `true` when that's not obvious.
+    pub fn may_coincide(&self, other: &Recurrence) -> bool {
+        match (self, other) {
+            (Recurrence::Odd, Recurrence::Even) | (Recurrence::Even, Recurrence::Odd) => false,
+            (Recurrence::Every { weeks: a, anchor: x }, Recurrence::Every { weeks: b, anchor: y })
+                if a == b => {
+                let distance = (week_start(*x) - week_start(*y)).num_weeks();
+                distance.rem_euclid(*a as i64) == 0
+            },
+            _ => true,
+        }
+    }
+}
+
+///The monday of the week containing `date`.
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+impl Display for Recurrence {
+    ///The format stored in the database, e.g. `odd` or `every:3:2022-10-03`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recurrence::Weekly => write!(f, "weekly"),
+            Recurrence::Odd => write!(f, "odd"),
+            Recurrence::Even => write!(f, "even"),
+            Recurrence::Every { weeks, anchor } => write!(f, "every:{}:{}", weeks, anchor),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || anyhow!(
+            "`{}` is not a recurrence, expected `weekly`, `odd`, `even` or `every:<weeks>:<anchor date>`", s);
+        match s.to_ascii_lowercase().as_str() {
+            "weekly" => Ok(Recurrence::Weekly),
+            "odd" => Ok(Recurrence::Odd),
+            "even" => Ok(Recurrence::Even),
+            other => {
+                let mut parts = other.strip_prefix("every:").ok_or_else(invalid)?.splitn(2, ':');
+                let weeks = parts.next()
+                    .and_then(|w| w.parse::<u32>().ok())
+                    .filter(|w| *w > 0)
+                    .ok_or_else(invalid)?;
+                let anchor = parts.next()
+                    .and_then(|a| a.parse::<NaiveDate>().ok())
+                    .ok_or_else(invalid)?;
+                if weeks == 1 {
+                    return Ok(Recurrence::Weekly);
+                }
+                Ok(Recurrence::Every { weeks, anchor })
+            },
+        }
+    }
+}
+
+impl Serialize for Recurrence {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
@@ -0,0 +1,168 @@
+use std::str::FromStr;
+use anyhow::{Result, anyhow};
+use serde_json::{json, Value};
+use crate::commands::CommandOutput;
+
+///Turns the output of a command into text that a front end can display.
+pub trait Renderer {
+    fn render(&self, output: &CommandOutput) -> Result<String>;
+}
+
+///Renders output as human readable text, the way the REPL has always printed it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextRenderer;
+
+impl Renderer for TextRenderer {
+    fn render(&self, output: &CommandOutput) -> Result<String> {
+        render_text(output, false)
+    }
+}
+
+///Renders output like `TextRenderer`, with events colored by their course or kind for terminals.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ColorRenderer;
+
+impl Renderer for ColorRenderer {
+    fn render(&self, output: &CommandOutput) -> Result<String> {
+        render_text(output, true)
+    }
+}
+
+fn render_text(output: &CommandOutput, colored: bool) -> Result<String> {
+    let event = |e: &crate::WeekEvent| if colored {
+        e.paint(e.to_string().trim_end()) + "\n"
+    } else {
+        e.to_string()
+    };
+    Ok(match output {
+        CommandOutput::None => String::new(),
+        CommandOutput::Message(message) => message.clone(),
+        CommandOutput::Event(e) => event(e),
+        CommandOutput::Events(event_s) if event_s.is_empty() => "No events found!".to_owned(),
+        CommandOutput::Events(event_s) => event_s.iter()
+            .map(event)
+            .collect::<Vec<String>>()
+            .join("\n"),
+        CommandOutput::Week(event_s) => crate::grid::render_with(event_s, colored),
+        CommandOutput::Table { headers, rows } => render_table(headers, rows),
+        CommandOutput::Many(outputs) => outputs.iter()
+            .map(|o| render_text(o, colored))
+            .collect::<Result<Vec<String>>>()?
+            .into_iter()
+            .filter(|o| !o.is_empty())
+            .collect::<Vec<String>>()
+            .join("\n"),
+        CommandOutput::Confirm { prompt, command } => format!("{} Run `{}` to confirm.", prompt, command),
+    })
+}
+
+fn render_table(headers: &[String], rows: &[Vec<String>]) -> String {
+    let mut widths = headers.iter()
+        .map(|h| h.chars().count())
+        .collect::<Vec<usize>>();
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            let len = cell.chars().count();
+            match widths.get_mut(i) {
+                Some(w) => *w = (*w).max(len),
+                None => widths.push(len),
+            }
+        }
+    }
+    let line = |cells: &[String]| cells.iter()
+        .zip(widths.iter())
+        .map(|(c, w)| format!("{:<width$}", c, width = w))
+        .collect::<Vec<String>>()
+        .join(" | ")
+        .trim_end()
+        .to_owned();
+
+    let mut ret = vec![line(headers)];
+    ret.push(widths.iter()
+        .map(|w| "-".repeat(*w))
+        .collect::<Vec<String>>()
+        .join("-+-"));
+    ret.extend(rows.iter().map(|r| line(r)));
+    ret.join("\n")
+}
+
+///Renders output as pretty-printed JSON, for scripts.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonRenderer;
+
+impl JsonRenderer {
+    fn value(&self, output: &CommandOutput) -> Result<Value> {
+        Ok(match output {
+            CommandOutput::None => Value::Null,
+            CommandOutput::Message(message) => json!({ "message": message }),
+            CommandOutput::Event(e) => serde_json::to_value(e)?,
+            CommandOutput::Events(event_s) => serde_json::to_value(event_s)?,
+            CommandOutput::Week(event_s) => {
+                let mut event_s = event_s.clone();
+                event_s.sort_by_key(|e| (e.day.num_days_from_monday(), e.starth));
+                let mut days: Vec<Value> = vec![];
+                for e in event_s {
+                    let day = e.day.to_string();
+                    if days.last().map(|d| d["day"] != day.as_str()).unwrap_or(true) {
+                        days.push(json!({ "day": day, "events": [] }));
+                    }
+                    if let Some(Value::Array(list)) = days.last_mut().map(|d| &mut d["events"]) {
+                        list.push(serde_json::to_value(e)?);
+                    }
+                }
+                Value::Array(days)
+            },
+            CommandOutput::Table { headers, rows } => Value::Array(rows.iter()
+                .map(|r| Value::Object(headers.iter()
+                    .cloned()
+                    .zip(r.iter().map(|c| Value::String(c.clone())))
+                    .collect()))
+                .collect()),
+            CommandOutput::Many(outputs) => Value::Array(outputs.iter()
+                .map(|o| self.value(o))
+                .collect::<Result<Vec<Value>>>()?),
+            CommandOutput::Confirm { prompt, command } => json!({ "confirm": prompt, "command": command }),
+        })
+    }
+}
+
+impl Renderer for JsonRenderer {
+    fn render(&self, output: &CommandOutput) -> Result<String> {
+        if let CommandOutput::None = output {
+            return Ok(String::new());
+        }
+        Ok(serde_json::to_string_pretty(&self.value(output)?)?)
+    }
+}
+
+///The built-in renderers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    ///Text with events colored by their kind.
+    Color,
+    Json,
+}
+
+impl OutputFormat {
+    pub fn renderer(self) -> Box<dyn Renderer> {
+        match self {
+            OutputFormat::Text => Box::new(TextRenderer),
+            OutputFormat::Color => Box::new(ColorRenderer),
+            OutputFormat::Json => Box::new(JsonRenderer),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "color" => Ok(Self::Color),
+            "json" => Ok(Self::Json),
+            _ => Err(anyhow!("unknown output format `{}`, expected `text`, `color` or `json`", s)),
+        }
+    }
+}
@@ -7,6 +7,123 @@ diesel::table! {
         day -> Text,
         starth -> Text,
         endh -> Text,
-        isLecture -> Integer,
+        recurrence -> Text,
+        term_id -> Nullable<Integer>,
+        date -> Nullable<Text>,
+        kind -> Text,
+        room_id -> Nullable<Integer>,
+        course_id -> Nullable<Integer>,
     }
 }
+
+diesel::table! {
+    instructors (id) {
+        id -> Nullable<Integer>,
+        name -> Text,
+        email -> Nullable<Text>,
+        office -> Nullable<Text>,
+        office_hours -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    notes (id) {
+        id -> Nullable<Integer>,
+        event_id -> Integer,
+        body -> Text,
+    }
+}
+
+diesel::table! {
+    rooms (id) {
+        id -> Nullable<Integer>,
+        building -> Text,
+        number -> Text,
+        notes -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    settings (key) {
+        key -> Text,
+        value -> Text,
+    }
+}
+
+diesel::table! {
+    terms (id) {
+        id -> Nullable<Integer>,
+        name -> Text,
+        start_date -> Text,
+        end_date -> Text,
+        active -> Integer,
+    }
+}
+
+diesel::table! {
+    courses (id) {
+        id -> Nullable<Integer>,
+        code -> Text,
+        title -> Text,
+        ects -> Double,
+        colour -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    event_instructors (event_id, instructor_id) {
+        event_id -> Integer,
+        instructor_id -> Integer,
+    }
+}
+
+diesel::table! {
+    event_tags (event_id, tag) {
+        event_id -> Integer,
+        tag -> Text,
+    }
+}
+
+diesel::table! {
+    exceptions (id) {
+        id -> Nullable<Integer>,
+        event_id -> Integer,
+        date -> Text,
+        cancelled -> Integer,
+        starth -> Nullable<Text>,
+        endh -> Nullable<Text>,
+        room_id -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    holidays (id) {
+        id -> Nullable<Integer>,
+        date -> Text,
+        name -> Text,
+    }
+}
+
+diesel::joinable!(events -> courses (course_id));
+diesel::joinable!(events -> rooms (room_id));
+diesel::joinable!(events -> terms (term_id));
+diesel::joinable!(event_instructors -> events (event_id));
+diesel::joinable!(event_instructors -> instructors (instructor_id));
+diesel::joinable!(event_tags -> events (event_id));
+diesel::joinable!(exceptions -> events (event_id));
+diesel::joinable!(exceptions -> rooms (room_id));
+diesel::joinable!(notes -> events (event_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    courses,
+    event_instructors,
+    event_tags,
+    events,
+    exceptions,
+    holidays,
+    instructors,
+    notes,
+    rooms,
+    settings,
+    terms,
+);
@@ -0,0 +1,54 @@
+use chrono::{Duration, NaiveTime, Weekday};
+use super::WeekEvent;
+
+///A gap between events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FreeSlot {
+    pub day: Weekday,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl FreeSlot {
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+///Finds the gaps of at least `min_duration` between the events of a single day,
+/// only looking at the part of the day between `window.0` and `window.1`.
+pub fn free_slots(event_s: &[WeekEvent],
+    day: Weekday,
+    min_duration: Duration,
+    window: (NaiveTime, NaiveTime)) -> Vec<FreeSlot> {
+    let mut busy = event_s.iter()
+        .filter(|e| e.day == day)
+        .map(|e| (e.starth, e.endh))
+        .collect::<Vec<(NaiveTime, NaiveTime)>>();
+    busy.sort();
+
+    let mut ret = vec![];
+    let mut cursor = window.0;
+    for (start, end) in busy {
+        if start > cursor {
+            push_slot(&mut ret, day, cursor, start.min(window.1), min_duration);
+        }
+        cursor = cursor.max(end);
+        if cursor >= window.1 {
+            break;
+        }
+    }
+    push_slot(&mut ret, day, cursor, window.1, min_duration);
+    ret
+}
+
+fn push_slot(slots: &mut Vec<FreeSlot>, day: Weekday, start: NaiveTime, end: NaiveTime, min_duration: Duration) {
+    if end > start && end - start >= min_duration {
+        slots.push(FreeSlot { day, start, end });
+    }
+}
+
+///Sorts slots from the longest to the shortest, earlier slots first when equally long.
+pub fn sort_by_length(slots: &mut [FreeSlot]) {
+    slots.sort_by_key(|s| (std::cmp::Reverse(s.duration()), s.day.num_days_from_monday(), s.start));
+}
@@ -1,7 +1,1316 @@
-use super::EventsManager;
+use super::{EventsManager, ConflictPolicy, ScheduleConflict};
+use super::commands::{CommandHandler, CommandModule, CommandOutput};
 
 #[test]
 fn connecting() {
     let manager = EventsManager::default();
     manager.unwrap();
-}
\ No newline at end of file
+}
+fn memory_manager() -> EventsManager {
+    EventsManager::custom(":memory:").unwrap()
+}
+
+///Command modules for testing the command framework.
+mod test_commands {
+    use anyhow::Result;
+    use syd_macros::*;
+    use crate::commands::{CommandContext, CommandOutput};
+
+    pub struct FlagModule;
+
+    #[command_module]
+    impl FlagModule {
+        #[command]
+        #[command_description("Echoes its arguments.")]
+        pub fn echo(_context: &mut CommandContext,
+            id: i32,
+            #[option] #[short('e')] endh: Option<chrono::NaiveTime>,
+            #[option] new_name: Option<String>,
+            #[flag] #[short('f')] force: bool,
+            #[flag] #[short('l')] long: bool) -> Result<CommandOutput> {
+            Ok(CommandOutput::message(format!("{} {:?} {:?} {} {}", id, endh, new_name, force, long)))
+        }
+    }
+
+    pub struct DefaultModule;
+
+    #[command_module]
+    impl DefaultModule {
+        #[command]
+        #[command_description("Echoes its arguments.")]
+        pub fn echo(_context: &mut CommandContext,
+            id: i32,
+            day: Option<chrono::Weekday>,
+            #[default(3)] amount: u32,
+            #[default("08:00")] from: chrono::NaiveTime,
+            until: Option<chrono::NaiveTime>,
+            #[option] #[default("weekly")] recurrence: crate::Recurrence) -> Result<CommandOutput> {
+            Ok(CommandOutput::message(format!("{} {:?} {} {} {:?} {}", id, day, amount, from, until, recurrence)))
+        }
+    }
+
+    pub struct RestModule;
+
+    #[command_module]
+    impl RestModule {
+        #[command]
+        #[command_description("Sums its arguments.")]
+        pub fn sum(_context: &mut CommandContext,
+            label: String,
+            values: Vec<i32>,
+            #[flag] #[short('n')] negate: bool) -> Result<CommandOutput> {
+            let sum = values.iter().sum::<i32>();
+            Ok(CommandOutput::message(format!("{} {}", label, if negate { -sum } else { sum })))
+        }
+        #[command]
+        #[command_description("Lists days.")]
+        pub fn days(_context: &mut CommandContext, days: Vec<chrono::Weekday>) -> Result<CommandOutput> {
+            Ok(CommandOutput::message(format!("{:?}", days)))
+        }
+    }
+
+    pub struct CsvModule;
+
+    #[command_module]
+    #[command_group("io csv")]
+    impl CsvModule {
+        #[command]
+        #[command_description("Pretends to export.")]
+        pub fn export(_context: &mut CommandContext, path: String) -> Result<CommandOutput> {
+            Ok(CommandOutput::message(format!("export {}", path)))
+        }
+        #[command]
+        #[command_group("set")]
+        #[command_description("Pretends to set the delimiter.")]
+        pub fn delimiter(_context: &mut CommandContext, delimiter: String) -> Result<CommandOutput> {
+            Ok(CommandOutput::message(format!("delimiter {}", delimiter)))
+        }
+    }
+
+    pub struct ClashModule;
+
+    #[command_module]
+    #[command_group("io")]
+    impl ClashModule {
+        #[command]
+        #[command_description("Has the name of a group.")]
+        pub fn csv(_context: &mut CommandContext) -> Result<CommandOutput> {
+            Ok(CommandOutput::None)
+        }
+    }
+}
+
+///A handler with the commands of `M` registered.
+fn handler_with<M: CommandModule>() -> CommandHandler {
+    let mut handler = CommandHandler::new(memory_manager());
+    handler.add_module::<M>().unwrap();
+    handler
+}
+
+///Executes `input`, which has to print a message or fail, and returns either as text.
+fn message(handler: &mut CommandHandler, input: &str) -> Result<String, String> {
+    match handler.execute(input.into()) {
+        Ok(CommandOutput::Message(m)) => Ok(m),
+        Ok(other) => panic!("unexpected output {:?}", other),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+///Id of the first event called `event_name`.
+fn id_of(manager: &mut EventsManager, event_name: &str) -> i32 {
+    manager.by_name(event_name.into()).unwrap()[0].id.unwrap()
+}
+
+#[test]
+fn ics_export_round_trip() {
+    use chrono::{NaiveDate, Weekday};
+    use super::NewWeekEvent;
+
+    let mut manager = memory_manager();
+    manager.add_events(vec![
+        NewWeekEvent::new("Algebra, part 1".into(), Weekday::Wed, "10:15:00", "11:45:00", true),
+        NewWeekEvent::new("Physics lab".into(), Weekday::Mon, "08:00:00", "09:30:00", false),
+    ], ConflictPolicy::Reject).unwrap();
+    manager.set_timezone(chrono_tz::Europe::Warsaw).unwrap();
+    let stored = manager.get_all().unwrap();
+
+    // the semester starts on a Tuesday, so Monday classes begin a week later
+    let start = NaiveDate::from_ymd_opt(2022, 10, 4).unwrap();
+    let calendar = manager.export_ics(start).unwrap();
+
+    assert!(calendar.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+    assert_eq!(calendar.matches("BEGIN:VEVENT").count(), stored.len());
+    for event in &stored {
+        let uid = format!("UID:event-{}@syd", event.id.unwrap());
+        let block = calendar.split("BEGIN:VEVENT")
+            .find(|b| b.contains(&uid))
+            .unwrap();
+        let date = super::ics::first_occurence(event, start);
+        assert!(block.contains(&format!("DTSTART;TZID=Europe/Warsaw:{}\r\n",
+            date.and_time(event.starth).format("%Y%m%dT%H%M%S"))));
+        assert!(block.contains(&format!("DTEND;TZID=Europe/Warsaw:{}\r\n",
+            date.and_time(event.endh).format("%Y%m%dT%H%M%S"))));
+    }
+    assert!(calendar.contains("DTSTART;TZID=Europe/Warsaw:20221005T101500\r\n"));
+    assert!(calendar.contains("DTSTART;TZID=Europe/Warsaw:20221010T080000\r\n"));
+    assert!(calendar.contains("RRULE:FREQ=WEEKLY;BYDAY=WE\r\n"));
+    assert!(calendar.contains("SUMMARY:Algebra\\, part 1\r\n"));
+
+    // exporting twice yields the same identifiers
+    let again = manager.export_ics(start).unwrap();
+    let uids = |c: &str| c.lines()
+        .filter(|l| l.starts_with("UID:"))
+        .map(|l| l.to_owned())
+        .collect::<Vec<String>>();
+    assert_eq!(uids(&calendar), uids(&again));
+}
+
+#[test]
+fn ics_import_round_trip() {
+    use chrono::{NaiveDate, Weekday};
+    use super::NewWeekEvent;
+
+    let mut source = memory_manager();
+    source.add_events(vec![
+        NewWeekEvent::new("Algebra; exercises".into(), Weekday::Wed, "10:15:00", "11:45:00", true),
+        NewWeekEvent::new("Physics lab".into(), Weekday::Mon, "08:00:00", "09:30:00", false),
+    ], ConflictPolicy::Reject).unwrap();
+    let start = NaiveDate::from_ymd_opt(2022, 10, 4).unwrap();
+    let calendar = source.export_ics(start).unwrap();
+
+    let mut target = memory_manager();
+    let imported = target.import_ics(&calendar).unwrap();
+    assert!(imported.skipped.is_empty());
+
+    //times keep their zone, so a timetable an hour behind sees everything an hour earlier
+    source.set_timezone(chrono_tz::Europe::Warsaw).unwrap();
+    let mut london = memory_manager();
+    london.set_timezone(chrono_tz::Europe::London).unwrap();
+    london.import_ics(&source.export_ics(start).unwrap()).unwrap();
+    let lab = london.by_name("Physics lab".into()).unwrap().remove(0);
+    assert_eq!(lab.starth.to_string(), "07:00:00");
+
+    let key = |e: &super::WeekEvent| (e.day.num_days_from_monday(), e.starth);
+    let mut expected = source.get_all().unwrap();
+    let mut actual = target.get_all().unwrap();
+    expected.sort_by_key(key);
+    actual.sort_by_key(key);
+    assert_eq!(expected.len(), actual.len());
+    for (e, a) in expected.iter().zip(actual.iter()) {
+        assert_eq!((&e.name, e.day, e.starth, e.endh, e.kind), 
+            (&a.name, a.day, a.starth, a.endh, a.kind));
+    }
+}
+
+#[test]
+fn ics_import_expands_and_reports() {
+    use chrono::Weekday;
+
+    let calendar = "BEGIN:VCALENDAR\r\n\
+        BEGIN:VEVENT\r\n\
+        SUMMARY:Seminar\r\n\
+        DTSTART;TZID=Europe/Warsaw:20221003T120000\r\n\
+        DURATION:PT1H30M\r\n\
+        RRULE:FREQ=WEEKLY;BYDAY=TH,MO,TH\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        SUMMARY:Monthly meet\r\n\
+        \x20ing\r\n\
+        DTSTART:20221003T120000\r\n\
+        DTEND:20221003T130000\r\n\
+        RRULE:FREQ=MONTHLY\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+    let mut manager = memory_manager();
+    manager.set_timezone(chrono_tz::Europe::Warsaw).unwrap();
+    let imported = manager.import_ics(calendar).unwrap();
+
+    assert_eq!(imported.skipped.len(), 1);
+    assert_eq!(imported.skipped[0].summary, "Monthly meeting");
+    let days = manager.get_all().unwrap()
+        .into_iter()
+        .map(|e| (e.day, e.endh.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(days, vec![
+        (Weekday::Mon, "13:30:00".to_owned()), 
+        (Weekday::Thu, "13:30:00".to_owned()),
+    ]);
+
+    //UTC times are taken in the timetable's timezone, Warsaw is 2 hours ahead in October
+    let mut manager = memory_manager();
+    manager.set_timezone(chrono_tz::Europe::Warsaw).unwrap();
+    manager.import_ics("BEGIN:VCALENDAR\r\n\
+        BEGIN:VEVENT\r\n\
+        SUMMARY:Lab\r\n\
+        DTSTART:20221004T080000Z\r\n\
+        DTEND:20221004T093000Z\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n").unwrap();
+    let lab = manager.get_all().unwrap().remove(0);
+    assert_eq!((lab.day, lab.starth.to_string()), (Weekday::Tue, "10:00:00".to_owned()));
+
+    //so are times in another zone, New York is 6 hours behind Warsaw
+    let mut manager = memory_manager();
+    manager.set_timezone(chrono_tz::Europe::Warsaw).unwrap();
+    let imported = manager.import_ics("BEGIN:VCALENDAR\r\n\
+        BEGIN:VEVENT\r\n\
+        SUMMARY:Call\r\n\
+        DTSTART;TZID=America/New_York:20221004T080000\r\n\
+        DTEND;TZID=America/New_York:20221004T090000\r\n\
+        RRULE:FREQ=WEEKLY\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        SUMMARY:Mars time\r\n\
+        DTSTART;TZID=Mars/Olympus:20221005T080000\r\n\
+        DTEND;TZID=Mars/Olympus:20221005T090000\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n").unwrap();
+    let call = manager.get_all().unwrap().remove(0);
+    assert_eq!((call.day, call.starth.to_string(), call.endh.to_string()), 
+        (Weekday::Tue, "14:00:00".to_owned(), "15:00:00".to_owned()));
+    assert_eq!(imported.skipped[0].reason, "unknown time zone `Mars/Olympus`");
+}
+
+#[test]
+fn ics_import_reports_bounded_series() {
+    let event = |rule: &str| format!("BEGIN:VCALENDAR\r\n\
+        BEGIN:VEVENT\r\n\
+        SUMMARY:Seminar\r\n\
+        DTSTART:20221003T120000\r\n\
+        DTEND:20221003T130000\r\n\
+        {}\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n", rule);
+    let mut manager = memory_manager();
+    for (rule, reason) in [
+        ("RRULE:FREQ=WEEKLY;COUNT=2", "recurrences with a number of occurences (COUNT) are not supported"),
+        ("RRULE:FREQ=WEEKLY;UNTIL=20221231T000000Z", "recurrences ending on a date (UNTIL) are not supported"),
+        ("RRULE:FREQ=WEEKLY\r\nEXDATE:20221010T120000", "recurrences with excluded dates (EXDATE) are not supported"),
+    ] {
+        let imported = manager.import_ics(&event(rule)).unwrap();
+        assert!(imported.events.is_empty());
+        assert_eq!(imported.skipped.len(), 1);
+        assert_eq!((imported.skipped[0].summary.as_str(), imported.skipped[0].reason.as_str()), ("Seminar", reason));
+    }
+    assert!(manager.get_all().unwrap().is_empty());
+}
+
+#[test]
+fn csv_round_trip() {
+    use chrono::Weekday;
+    use super::{NewWeekEvent, EventKind};
+
+    let mut source = memory_manager();
+    source.add_events(vec![
+        NewWeekEvent::new("Algebra, lecture".into(), Weekday::Wed, "10:15:00", "11:45:00", true),
+        NewWeekEvent::new("Physics lab".into(), Weekday::Mon, "08:00:00", "09:30:00", false),
+    ], ConflictPolicy::Reject).unwrap();
+    let mut buffer = vec![];
+    source.export_csv(&mut buffer).unwrap();
+
+    let mut target = memory_manager();
+    assert_eq!(target.import_csv(buffer.as_slice()).unwrap(), 2);
+    let names = target.get_all().unwrap()
+        .into_iter()
+        .map(|e| (e.name, e.day, e.kind))
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec![
+        ("Physics lab".to_owned(), Weekday::Mon, EventKind::Other),
+        ("Algebra, lecture".to_owned(), Weekday::Wed, EventKind::Lecture),
+    ]);
+}
+
+#[test]
+fn csv_import_reports_rows() {
+    use super::csv_io::{CsvImportError, CsvRowError};
+
+    let input = "name,day,starth,endh,is_lecture\n\
+        Algebra,Mon,10:00,11:30,true\n\
+        Physics,Someday,10:00,11:30,false\n\
+        Chemistry,Tue,25:00,11:30,maybe\n";
+    let mut manager = memory_manager();
+    let err = manager.import_csv(input.as_bytes()).unwrap_err();
+    let err = err.downcast::<CsvImportError>().unwrap();
+    let columns = err.rows.iter()
+        .map(|CsvRowError { line, column, .. }| (*line, column.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(columns, vec![(3, "day"), (4, "starth"), (4, "is_lecture")]);
+    assert!(manager.get_all().unwrap().is_empty());
+
+    let err = manager.import_csv("name,day\nAlgebra,Mon\n".as_bytes()).unwrap_err();
+    assert!(err.to_string().contains("starth, endh, kind"));
+}
+
+#[test]
+fn week_event_json() {
+    use chrono::Weekday;
+    use super::NewWeekEvent;
+
+    let mut manager = memory_manager();
+    manager.add_event(NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:15:00", "11:45:00", true), ConflictPolicy::Reject)
+        .unwrap();
+    let event = manager.get_all().unwrap().remove(0);
+    let json = serde_json::to_value(&event).unwrap();
+    assert_eq!(json, serde_json::json!({
+        "id": event.id,
+        "name": "Algebra",
+        "day": "Mon",
+        "starth": "10:15:00",
+        "endh": "11:45:00",
+        "kind": "lecture",
+        "recurrence": "weekly",
+        "term_id": null,
+        "date": null,
+        "room": null,
+        "instructors": [],
+        "course": null,
+        "tags": [],
+        "notes": [],
+    }));
+}
+
+#[test]
+fn commands_return_output() {
+    use chrono::Weekday;
+    use super::NewWeekEvent;
+    use super::commands::*;
+    use super::render::{Renderer, TextRenderer, JsonRenderer};
+
+    let mut manager = memory_manager();
+    manager.add_event(NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:15:00", "11:45:00", true), ConflictPolicy::Reject)
+        .unwrap();
+    let mut handler = CommandHandler::new(manager);
+    handler.add_command(Command {
+        name: "count".into(),
+        group: vec![],
+        desc: None,
+        args: vec![],
+        function: Box::new(|context, _| {
+            let event_s = context.manager().get_all()?;
+            Ok(CommandOutput::Table {
+                headers: vec!["name".into(), "count".into()],
+                rows: vec![vec!["events".into(), event_s.len().to_string()]],
+            })
+        }),
+    }).unwrap();
+
+    let output = handler.execute("count".into()).unwrap();
+    assert_eq!(TextRenderer.render(&output).unwrap(), 
+        "name   | count\n-------+------\nevents | 1");
+    assert_eq!(JsonRenderer.render(&output).unwrap(), 
+        "[\n  {\n    \"count\": \"1\",\n    \"name\": \"events\"\n  }\n]");
+    assert_eq!(JsonRenderer.render(&CommandOutput::message("hi")).unwrap(),
+        "{\n  \"message\": \"hi\"\n}");
+    assert!(handler.execute("missing".into()).is_err());
+}
+
+#[test]
+fn week_grid_places_overlaps_side_by_side() {
+    use chrono::Weekday;
+    use super::NewWeekEvent;
+
+    let mut manager = memory_manager();
+    manager.add_events(vec![
+        NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:00:00", "11:30:00", true),
+        NewWeekEvent::new("Physics lab".into(), Weekday::Mon, "11:00:00", "12:00:00", false),
+        NewWeekEvent::new("Sport".into(), Weekday::Fri, "12:00:00", "13:00:00", false),
+    ], ConflictPolicy::Allow).unwrap();
+    let grid = super::grid::render(&manager.get_all().unwrap());
+    let lines = grid.lines().collect::<Vec<&str>>();
+
+    assert!(lines[0].starts_with("      | Mon "));
+    assert!(lines[0].ends_with("| Sun"));
+    // rows span from the earliest start to the latest end
+    assert!(lines[2].starts_with("10:00 | *Algebra "));
+    assert!(lines[3].starts_with("11:00 | 10:00-11:30   Physics lab "));
+    assert!(lines[4].starts_with("12:00 |               "));
+    assert!(lines[4].contains("| Sport"));
+    assert_eq!(lines.len(), 2 + 3 + 2);
+    assert_eq!(super::grid::render(&[]), "No events found!");
+
+    // back-to-back classes sharing the 09:00 row are both drawn
+    let mut manager = memory_manager();
+    manager.add_events(vec![
+        NewWeekEvent::new("Chemistry".into(), Weekday::Mon, "08:00:00", "09:30:00", false),
+        NewWeekEvent::new("Physics".into(), Weekday::Mon, "09:45:00", "11:15:00", true),
+    ], ConflictPolicy::Reject).unwrap();
+    let grid = super::grid::render(&manager.get_all().unwrap());
+    let lines = grid.lines().collect::<Vec<&str>>();
+    assert!(lines[2].starts_with("08:00 | Chemistry "));
+    assert!(lines[3].starts_with("09:00 | 08:00-09:30   *Physics "));
+    assert!(lines[4].starts_with("10:00 |               09:45-11:15 "));
+}
+
+#[test]
+fn conflicts_are_rejected_unless_forced() {
+    use chrono::Weekday;
+    use super::NewWeekEvent;
+    use super::models::UpdatedWeekEvent;
+
+    let mut manager = memory_manager();
+    manager.add_events(vec![
+        NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:00:00", "11:30:00", true),
+        NewWeekEvent::new("Physics".into(), Weekday::Mon, "11:30:00", "13:00:00", false),
+        NewWeekEvent::new("Chemistry".into(), Weekday::Tue, "10:00:00", "11:30:00", false),
+    ], ConflictPolicy::Reject).unwrap();
+
+    let clash = NewWeekEvent::new("Sport".into(), Weekday::Mon, "11:00:00", "12:00:00", false);
+    let err = manager.add_event(clash.clone(), ConflictPolicy::Reject).unwrap_err();
+    let err = err.downcast::<ScheduleConflict>().unwrap();
+    let mut names = err.conflicts.iter().map(|e| e.name.as_str()).collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec!["Algebra", "Physics"]);
+    assert_eq!(manager.get_all().unwrap().len(), 3);
+
+    // moving Chemistry onto Monday morning clashes with Algebra, but not with itself
+    let chemistry = manager.by_name("Chemistry".into()).unwrap().remove(0);
+    let update = UpdatedWeekEvent {
+        id: chemistry.id.unwrap(),
+        day: Some("Mon".into()),
+        ..Default::default()
+    };
+    assert!(manager.change_event(update.clone(), ConflictPolicy::Reject).is_err());
+    let shorter = UpdatedWeekEvent { endh: Some("11:00:00".into()), day: None, ..update.clone() };
+    manager.change_event(shorter, ConflictPolicy::Reject).unwrap();
+
+    assert!(manager.find_conflicts().unwrap().is_empty());
+    manager.add_event(clash, ConflictPolicy::Allow).unwrap();
+    manager.change_event(update, ConflictPolicy::Allow).unwrap();
+    assert_eq!(manager.find_conflicts().unwrap().len(), 3);
+}
+
+#[test]
+fn invalid_events_are_rejected() {
+    use chrono::Weekday;
+    use diesel::RunQueryDsl;
+    use super::{NewWeekEvent, ValidationError};
+    use super::models::UpdatedWeekEvent;
+
+    let mut manager = memory_manager();
+    let backwards = NewWeekEvent::new("Algebra".into(), Weekday::Mon, "11:30:00", "10:00:00", true);
+    let err = manager.add_event(backwards, ConflictPolicy::Allow).unwrap_err();
+    assert!(matches!(err.downcast::<ValidationError>().unwrap(), ValidationError::InvalidRange { .. }));
+    let unnamed = NewWeekEvent::new("  ".into(), Weekday::Mon, "10:00:00", "11:30:00", true);
+    let err = manager.add_events(vec![unnamed], ConflictPolicy::Allow).unwrap_err();
+    assert_eq!(err.downcast::<ValidationError>().unwrap(), ValidationError::EmptyName);
+
+    manager.add_event(NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:00:00", "11:30:00", true), 
+        ConflictPolicy::Reject).unwrap();
+    let stored = manager.get_all().unwrap().remove(0);
+    // only the start changes, but the merged row would end before it starts
+    let update = UpdatedWeekEvent {
+        id: stored.id.unwrap(),
+        starth: Some("12:00:00".into()),
+        ..Default::default()
+    };
+    assert!(manager.change_event(update, ConflictPolicy::Allow).is_err());
+    assert_eq!(manager.get_event(stored.id.unwrap()).unwrap().starth, stored.starth);
+
+    // the database refuses invalid rows on its own as well
+    let raw = diesel::sql_query(
+        "INSERT INTO events (name, day, starth, endh, kind) VALUES ('Physics', 'Tue', '12:00:00', '08:00:00', 'other')")
+        .execute(&mut manager.connection);
+    assert!(raw.is_err());
+    let raw = diesel::sql_query(
+        "INSERT INTO events (name, day, starth, endh, kind) VALUES ('', 'Tue', '08:00:00', '12:00:00', 'other')")
+        .execute(&mut manager.connection);
+    assert!(raw.is_err());
+}
+
+#[test]
+fn free_slots_between_events() {
+    use chrono::{Duration, NaiveTime, Weekday};
+    use super::NewWeekEvent;
+
+    let mut manager = memory_manager();
+    manager.add_events(vec![
+        NewWeekEvent::new("Algebra".into(), Weekday::Mon, "07:00:00", "09:00:00", true),
+        NewWeekEvent::new("Physics".into(), Weekday::Mon, "10:00:00", "12:00:00", false),
+        NewWeekEvent::new("Lab".into(), Weekday::Mon, "12:15:00", "19:00:00", false),
+    ], ConflictPolicy::Reject).unwrap();
+    let time = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+    let window = (time(8, 0), time(20, 0));
+
+    let monday = manager.free_slots(Some(Weekday::Mon), Duration::minutes(30), window).unwrap();
+    let monday = monday.iter().map(|s| (s.start, s.end)).collect::<Vec<_>>();
+    assert_eq!(monday, vec![(time(9, 0), time(10, 0)), (time(19, 0), time(20, 0))]);
+
+    let week = manager.free_slots(None, Duration::minutes(10), window).unwrap();
+    assert_eq!(week.len(), 6 + 3);
+    assert_eq!(week[0].day, Weekday::Tue);
+    assert_eq!(week[0].duration(), Duration::hours(12));
+    assert_eq!((week[8].start, week[8].end), (time(12, 0), time(12, 15)));
+}
+
+#[test]
+fn alternating_weeks() {
+    use chrono::{NaiveDate, Weekday};
+    use super::{NewWeekEvent, Recurrence};
+
+    // 2022-10-03 is the Monday of ISO week 40
+    let date = |d| NaiveDate::from_ymd_opt(2022, 10, d).unwrap();
+    let every_third: Recurrence = "every:3:2022-10-05".parse().unwrap();
+    assert!(Recurrence::Even.occurs_on(date(3)) && !Recurrence::Odd.occurs_on(date(9)));
+    assert!(Recurrence::Odd.occurs_on(date(10)));
+    assert!(every_third.occurs_on(date(3)) && every_third.occurs_on(date(24)));
+    assert!(!every_third.occurs_on(date(10)) && !every_third.occurs_on(date(17)));
+    assert_eq!(every_third.to_string(), "every:3:2022-10-05");
+    assert!("fortnightly".parse::<Recurrence>().is_err());
+
+    let mut manager = memory_manager();
+    manager.add_events(vec![
+        NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:00:00", "11:30:00", true)
+            .with_recurrence(Recurrence::Odd),
+        NewWeekEvent::new("Physics".into(), Weekday::Mon, "10:00:00", "11:30:00", false)
+            .with_recurrence(Recurrence::Even),
+        NewWeekEvent::new("Sport".into(), Weekday::Mon, "12:00:00", "13:00:00", false),
+    ], ConflictPolicy::Reject).unwrap();
+    let clash = NewWeekEvent::new("Lab".into(), Weekday::Mon, "11:00:00", "12:00:00", false)
+        .with_recurrence(Recurrence::Odd);
+    let err = manager.add_event(clash, ConflictPolicy::Reject).unwrap_err();
+    let err = err.downcast::<ScheduleConflict>().unwrap();
+    assert_eq!(err.conflicts.len(), 1);
+    assert_eq!(err.conflicts[0].name, "Algebra");
+
+    let names = |event_s: Vec<super::WeekEvent>| {
+        let mut n = event_s.into_iter().map(|e| e.name).collect::<Vec<String>>();
+        n.sort();
+        n
+    };
+    assert_eq!(names(manager.by_date(date(3)).unwrap()), vec!["Physics", "Sport"]);
+    assert_eq!(names(manager.by_date(date(10)).unwrap()), vec!["Algebra", "Sport"]);
+    assert!(manager.by_date(date(11)).unwrap().is_empty());
+    //a week shows only the alternating events of its own parity
+    assert_eq!(names(manager.by_week(date(7)).unwrap()), vec!["Physics", "Sport"]);
+
+    // alternating events survive an iCalendar round trip as an interval of two weeks
+    manager.set_timezone(chrono_tz::UTC).unwrap();
+    let calendar = manager.export_ics(date(3)).unwrap();
+    assert!(calendar.contains("DTSTART;TZID=UTC:20221010T100000\r\nDTEND;TZID=UTC:20221010T113000\r\n\
+        RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO\r\nSUMMARY:Algebra"));
+    let mut target = memory_manager();
+    target.set_timezone(chrono_tz::UTC).unwrap();
+    target.import_ics(&calendar).unwrap();
+    assert_eq!(names(target.by_date(date(3)).unwrap()), vec!["Physics", "Sport"]);
+    assert_eq!(names(target.by_date(date(10)).unwrap()), vec!["Algebra", "Sport"]);
+}
+
+#[test]
+fn terms_scope_events() {
+    use chrono::{NaiveDate, Weekday};
+    use super::{NewWeekEvent, NewTerm};
+
+    let mut manager = memory_manager();
+    manager.add_event(NewWeekEvent::new("Gym".into(), Weekday::Mon, "18:00:00", "19:00:00", false), ConflictPolicy::Reject)
+        .unwrap();
+    manager.add_term(NewTerm {
+        name: "Winter".into(),
+        start: NaiveDate::from_ymd_opt(2022, 10, 1).unwrap(),
+        end: NaiveDate::from_ymd_opt(2023, 1, 31).unwrap(),
+    }).unwrap();
+    manager.add_term(NewTerm {
+        name: "Summer".into(),
+        start: NaiveDate::from_ymd_opt(2023, 2, 20).unwrap(),
+        end: NaiveDate::from_ymd_opt(2023, 6, 15).unwrap(),
+    }).unwrap();
+    assert!(manager.add_term(NewTerm {
+        name: "Backwards".into(),
+        start: NaiveDate::from_ymd_opt(2023, 6, 15).unwrap(),
+        end: NaiveDate::from_ymd_opt(2023, 2, 20).unwrap(),
+    }).is_err());
+    let terms = manager.get_terms().unwrap();
+    assert_eq!(terms.len(), 2);
+    let (winter, summer) = (terms[0].id, terms[1].id);
+
+    manager.set_active_term(Some(winter)).unwrap();
+    manager.add_event(NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:00:00", "11:00:00", true), ConflictPolicy::Reject)
+        .unwrap();
+    manager.set_active_term(Some(summer)).unwrap();
+    assert_eq!(manager.active_term().unwrap().unwrap().name, "Summer");
+    //a different term's event doesn't clash
+    manager.add_event(NewWeekEvent::new("Physics".into(), Weekday::Mon, "10:00:00", "11:00:00", true), ConflictPolicy::Reject)
+        .unwrap();
+    let names = |events: Vec<super::WeekEvent>| {
+        let mut names = events.into_iter().map(|e| e.name).collect::<Vec<String>>();
+        names.sort();
+        names
+    };
+    assert_eq!(names(manager.get_all().unwrap()), vec!["Gym", "Physics"]);
+
+    manager.set_active_term(Some(winter)).unwrap();
+    assert_eq!(names(manager.by_day(Weekday::Mon).unwrap()), vec!["Algebra", "Gym"]);
+    //2022-11-07 is a monday during the winter term, 2023-02-06 one after it ended
+    assert_eq!(names(manager.by_date(NaiveDate::from_ymd_opt(2022, 11, 7).unwrap()).unwrap()), vec!["Algebra", "Gym"]);
+    assert_eq!(names(manager.by_date(NaiveDate::from_ymd_opt(2023, 2, 6).unwrap()).unwrap()), vec!["Gym"]);
+
+    manager.set_active_term(None).unwrap();
+    assert!(manager.active_term().unwrap().is_none());
+    assert_eq!(manager.get_all().unwrap().len(), 3);
+    assert!(manager.set_active_term(Some(100)).is_err());
+}
+
+#[test]
+fn exceptions_and_holidays() {
+    use chrono::{NaiveDate, NaiveTime, Weekday};
+    use super::{NewWeekEvent, EventException, Change, Holiday, Room, ValidationError};
+
+    let mut manager = memory_manager();
+    manager.add_events(vec![
+        NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:00:00", "11:00:00", true),
+        NewWeekEvent::new("Physics".into(), Weekday::Mon, "12:00:00", "13:00:00", true),
+    ], ConflictPolicy::Reject).unwrap();
+    let events = manager.get_all().unwrap();
+    let algebra = events.iter().find(|e| e.name == "Algebra").unwrap().id.unwrap();
+    let physics = events.iter().find(|e| e.name == "Physics").unwrap().id.unwrap();
+    let monday = NaiveDate::from_ymd_opt(2022, 11, 7).unwrap();
+    let next_monday = NaiveDate::from_ymd_opt(2022, 11, 14).unwrap();
+    let names = |events: Vec<super::WeekEvent>| events.into_iter().map(|e| e.name).collect::<Vec<String>>();
+
+    manager.add_exception(EventException { id: None, event_id: algebra, date: monday, change: Change::Cancelled })
+        .unwrap();
+    //physics moves before algebra's usual time, `by_date` keeps the day sorted
+    manager.add_exception(EventException {
+        id: None,
+        event_id: physics,
+        date: next_monday,
+        change: Change::Moved {
+            starth: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+            endh: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            room: Some(Room::new("C-13", "0.31")),
+        },
+    }).unwrap();
+    assert_eq!(names(manager.by_date(monday).unwrap()), vec!["Physics"]);
+    let moved = manager.by_date(next_monday).unwrap();
+    assert_eq!(names(moved.clone()), vec!["Physics", "Algebra"]);
+    assert_eq!(moved[0].starth, NaiveTime::from_hms_opt(8, 0, 0).unwrap());
+    assert_eq!(moved[0].room.as_ref().map(|r| r.to_string()), Some("C-13 0.31".to_owned()));
+    assert!(manager.get_rooms().unwrap()[0].id.is_some());
+    //the weekly pattern itself stays untouched
+    assert_eq!(manager.get_event(physics).unwrap().starth, NaiveTime::from_hms_opt(12, 0, 0).unwrap());
+
+    //not a monday
+    assert!(manager.add_exception(EventException {
+        id: None,
+        event_id: algebra,
+        date: NaiveDate::from_ymd_opt(2022, 11, 8).unwrap(),
+        change: Change::Cancelled,
+    }).is_err());
+
+    assert!(manager.remove_exception(algebra, monday).unwrap());
+    assert_eq!(names(manager.by_date(monday).unwrap()), vec!["Algebra", "Physics"]);
+
+    manager.add_holiday(Holiday { id: None, date: monday, name: "Day off".into() }).unwrap();
+    assert!(manager.by_date(monday).unwrap().is_empty());
+    assert!(manager.remove_holiday(monday).unwrap());
+    assert_eq!(manager.by_date(monday).unwrap().len(), 2);
+
+    //a range is added as a whole or not at all
+    let wednesday = NaiveDate::from_ymd_opt(2022, 11, 9).unwrap();
+    manager.add_holiday(Holiday { id: None, date: wednesday, name: "Day off".into() }).unwrap();
+    assert!(manager.add_holidays(monday..=wednesday, "Break").is_err());
+    assert_eq!(manager.get_holidays().unwrap().len(), 1);
+    assert!(manager.add_holidays(wednesday..=monday, "Break").unwrap_err()
+        .downcast::<ValidationError>().is_ok());
+    assert_eq!(manager.add_holidays(monday..=monday + chrono::Duration::days(1), "Break").unwrap(), 2);
+    assert!(manager.by_date(monday).unwrap().is_empty());
+
+    //exceptions go away together with their event
+    manager.delete_event(physics).unwrap();
+    assert!(manager.get_exceptions().unwrap().is_empty());
+}
+
+#[test]
+fn delete_events() {
+    use chrono::Weekday;
+    use super::NewWeekEvent;
+
+    let mut manager = memory_manager();
+    manager.add_events(vec![
+        NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:00:00", "11:00:00", true),
+        NewWeekEvent::new("Physics".into(), Weekday::Mon, "12:00:00", "13:00:00", true),
+        NewWeekEvent::new("Sport".into(), Weekday::Fri, "12:00:00", "13:00:00", false),
+    ], ConflictPolicy::Reject).unwrap();
+    let ids = manager.get_all().unwrap().iter().filter_map(|e| e.id).collect::<Vec<i32>>();
+
+    //an unknown id keeps the others from being deleted
+    let err = manager.delete_events(&[ids[0], 99, 98, 99]).unwrap_err();
+    assert_eq!(err.to_string(), "no entries with ids 98, 99");
+    assert!(manager.check_events(&[ids[2], 97]).is_err());
+    assert!(manager.check_events(&ids).is_ok());
+    assert_eq!(manager.get_all().unwrap().len(), 3);
+    assert_eq!(manager.delete_events(&ids[..2]).unwrap(), 2);
+    assert_eq!(manager.get_all().unwrap()[0].name, "Sport");
+}
+
+#[test]
+fn dated_events() {
+    use chrono::{NaiveDate, Weekday};
+    use super::NewWeekEvent;
+
+    let mut manager = memory_manager();
+    let exam_day = NaiveDate::from_ymd_opt(2023, 1, 30).unwrap();
+    manager.add_events(vec![
+        NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:00:00", "11:00:00", true),
+        NewWeekEvent::new("Algebra exam".into(), Weekday::Mon, "08:00:00", "09:30:00", false)
+            .on_date(exam_day),
+    ], ConflictPolicy::Reject).unwrap();
+
+    let names = |events: Vec<super::WeekEvent>| events.into_iter().map(|e| e.name).collect::<Vec<String>>();
+    assert_eq!(names(manager.by_date(exam_day).unwrap()), vec!["Algebra exam", "Algebra"]);
+    assert_eq!(names(manager.by_date(exam_day + chrono::Duration::weeks(1)).unwrap()), vec!["Algebra"]);
+    //a week only holds the dated events of its own dates
+    assert_eq!(names(manager.by_week(exam_day + chrono::Duration::days(3)).unwrap()), vec!["Algebra exam", "Algebra"]);
+    assert_eq!(names(manager.by_week(exam_day - chrono::Duration::days(1)).unwrap()), vec!["Algebra"]);
+
+    //a makeup class clashes with the weekly lecture, but only on its own date
+    let makeup = NewWeekEvent::new("Makeup".into(), Weekday::Mon, "10:30:00", "12:00:00", true);
+    assert!(manager.add_event(makeup.clone().on_date(exam_day), ConflictPolicy::Reject).is_err());
+    assert!(manager.add_event(NewWeekEvent::new("Consultation".into(), Weekday::Mon, "09:00:00", "09:30:00", false)
+        .on_date(exam_day - chrono::Duration::weeks(1)), ConflictPolicy::Reject).is_ok());
+
+    //the date has to fall on the event's day
+    let mut wrong_day = makeup.on_date(exam_day);
+    wrong_day.day = Weekday::Tue;
+    assert!(manager.add_event(wrong_day, ConflictPolicy::Allow).is_err());
+
+    manager.set_timezone(chrono_tz::UTC).unwrap();
+    let calendar = manager.export_ics(NaiveDate::from_ymd_opt(2022, 10, 3).unwrap()).unwrap();
+    assert!(calendar.contains("DTSTART;TZID=UTC:20230130T080000\r\n"));
+    let mut imported = memory_manager();
+    imported.set_timezone(chrono_tz::UTC).unwrap();
+    imported.import_ics(&calendar).unwrap();
+    let exam = imported.by_name("Algebra exam".into()).unwrap().remove(0);
+    assert_eq!(exam.date, Some(exam_day));
+
+    let mut csv = vec![];
+    manager.export_csv(&mut csv).unwrap();
+    let mut imported = memory_manager();
+    imported.import_csv(csv.as_slice()).unwrap();
+    let exam = imported.by_name("Algebra exam".into()).unwrap().remove(0);
+    assert_eq!(exam.date, Some(exam_day));
+}
+
+#[test]
+fn event_kinds() {
+    use chrono::Weekday;
+    use diesel::RunQueryDsl;
+    use super::{NewWeekEvent, EventKind};
+    use super::models::UpdatedWeekEvent;
+    use super::render::{Renderer, ColorRenderer};
+    use super::commands::CommandOutput;
+
+    let mut manager = memory_manager();
+    manager.add_events(vec![
+        NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:00:00", "11:00:00", EventKind::Lecture),
+        NewWeekEvent::new("Physics lab".into(), Weekday::Tue, "10:00:00", "11:00:00", EventKind::Lab),
+        NewWeekEvent::new("Sport".into(), Weekday::Wed, "10:00:00", "11:00:00", false),
+    ], ConflictPolicy::Reject).unwrap();
+    let labs = manager.by_kind(EventKind::Lab).unwrap();
+    assert_eq!(labs.len(), 1);
+    assert_eq!(labs[0].name, "Physics lab");
+    assert_eq!(manager.by_kind(EventKind::Other).unwrap()[0].name, "Sport");
+
+    let sport = manager.by_name("Sport".into()).unwrap().remove(0);
+    manager.change_event(UpdatedWeekEvent {
+        id: sport.id.unwrap(),
+        kind: Some("exercise".into()),
+        ..Default::default()
+    }, ConflictPolicy::Reject).unwrap();
+    assert_eq!(manager.get_event(sport.id.unwrap()).unwrap().kind, EventKind::Exercise);
+    assert!("LAB".parse::<EventKind>().is_ok());
+    assert!("workshop".parse::<EventKind>().is_err());
+
+    //the database rejects kinds it doesn't know
+    assert!(diesel::sql_query(
+        "INSERT INTO events (name, day, starth, endh, kind) VALUES ('Chess', 'Thu', '08:00:00', '12:00:00', 'workshop')")
+        .execute(&mut manager.connection)
+        .is_err());
+
+    let colored = ColorRenderer.render(&CommandOutput::Events(labs)).unwrap();
+    assert!(colored.starts_with(&format!("\x1b[{}m", EventKind::Lab.color())));
+    let grid = super::grid::render_with(&manager.get_all().unwrap(), true);
+    assert!(grid.contains(&EventKind::Lecture.paint(&format!("{:<13}", "*Algebra"))));
+    assert!(grid.ends_with("* lecture  + lab  # seminar  = exercise  ! exam"));
+}
+
+#[test]
+fn invalid_events_are_repaired_by_migration() {
+    use diesel::{Connection, RunQueryDsl};
+    use diesel::sqlite::SqliteConnection;
+    use diesel_migrations::MigrationHarness;
+
+    let mut connection = SqliteConnection::establish(":memory:").unwrap();
+    //everything up to the migration that introduced the constraints
+    while connection.pending_migrations(super::MIGRATIONS).unwrap()[0].name().to_string()
+        != "2022-11-20-120000_validate_events" {
+        connection.run_next_migration(super::MIGRATIONS).unwrap();
+    }
+    diesel::sql_query("INSERT INTO events (name, day, starth, endh, isLecture) VALUES \
+        ('  ', 'Mon', '10:00:00', '11:00:00', 1), ('Physics', 'Tue', '12:00:00', '08:00:00', 0), \
+        ('Lab', 'Wed', '09:00:00', '09:00:00', 0), ('Party', 'Fri', '23:30:00', '23:30:00', 0), \
+        ('Ghost', 'Sat', '23:59:59', '23:59:59', 0)")
+        .execute(&mut connection)
+        .unwrap();
+    connection.run_pending_migrations(super::MIGRATIONS).unwrap();
+
+    let mut manager = super::EventsManager { connection };
+    let hours = manager.get_all().unwrap()
+        .into_iter()
+        .map(|e| format!("{} {}-{}", e.name, e.starth, e.endh))
+        .collect::<Vec<_>>();
+    assert_eq!(hours, vec![
+        "Unnamed event 10:00:00-11:00:00",
+        "Physics 08:00:00-12:00:00",
+        "Lab 09:00:00-10:00:00",
+        "Party 23:30:00-23:59:59",
+    ]);
+}
+
+#[test]
+fn is_lecture_migrates_to_kind() {
+    use diesel::{Connection, RunQueryDsl};
+    use diesel::sqlite::SqliteConnection;
+    use diesel_migrations::MigrationHarness;
+    use super::EventKind;
+
+    let mut connection = SqliteConnection::establish(":memory:").unwrap();
+    //everything up to the migration that introduced kinds
+    while connection.pending_migrations(super::MIGRATIONS).unwrap()[0].name().to_string()
+        != "2022-12-28-120000_event_kinds" {
+        connection.run_next_migration(super::MIGRATIONS).unwrap();
+    }
+    diesel::sql_query("INSERT INTO events (name, day, starth, endh, isLecture) VALUES \
+        ('Algebra', 'Mon', '10:00:00', '11:00:00', 1), ('Sport', 'Tue', '10:00:00', '11:00:00', 0)")
+        .execute(&mut connection)
+        .unwrap();
+    connection.run_pending_migrations(super::MIGRATIONS).unwrap();
+
+    let mut manager = super::EventsManager { connection };
+    let kinds = manager.get_all().unwrap()
+        .into_iter()
+        .map(|e| (e.name, e.kind))
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, vec![
+        ("Algebra".to_owned(), EventKind::Lecture),
+        ("Sport".to_owned(), EventKind::Other),
+    ]);
+}
+
+#[test]
+fn rooms() {
+    use chrono::Weekday;
+    use super::{NewWeekEvent, Room, RoomConflict, EventKind};
+    use super::models::UpdatedWeekEvent;
+
+    let mut manager = memory_manager();
+    let lab = Room::new("C-13", "2.41");
+    //a rejected batch stores none of its rooms
+    assert!(manager.add_events(vec![
+        NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:00:00", "11:30:00", EventKind::Lecture)
+            .in_room(lab.clone()),
+        NewWeekEvent::new("Physics".into(), Weekday::Mon, "13:00:00", "12:00:00", EventKind::Lecture)
+            .in_room(Room::new("B-2", "7")),
+    ], ConflictPolicy::Reject).is_err());
+    assert!(manager.add_events(vec![
+        NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:00:00", "11:30:00", EventKind::Lecture)
+            .in_room(lab.clone()),
+        NewWeekEvent::new("Physics".into(), Weekday::Mon, "11:00:00", "12:00:00", EventKind::Lecture)
+            .in_room(Room::new("B-2", "7")),
+    ], ConflictPolicy::Reject).is_err());
+    assert!(manager.get_rooms().unwrap().is_empty());
+    manager.add_events(vec![
+        NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:00:00", "11:30:00", EventKind::Lecture)
+            .in_room("A-1 101".parse().unwrap()),
+        NewWeekEvent::new("Physics lab".into(), Weekday::Mon, "12:00:00", "13:00:00", EventKind::Lab)
+            .in_room(lab.clone()),
+    ], ConflictPolicy::Reject).unwrap();
+    assert_eq!(manager.get_rooms().unwrap().len(), 2);
+
+    let algebra = manager.by_room("a-1 101").unwrap().remove(0);
+    assert_eq!(algebra.name, "Algebra");
+    assert!(algebra.to_string().contains("Room: A-1 101\n"));
+    assert_eq!(manager.by_room("C-13").unwrap().len(), 1);
+    assert_eq!(manager.by_room("2.41").unwrap().len(), 1);
+    assert!(manager.by_room("B-2").unwrap().is_empty());
+
+    //forcing an overlap doesn't make a taken room free
+    let clash = NewWeekEvent::new("Chemistry lab".into(), Weekday::Mon, "12:30:00", "13:30:00", EventKind::Lab)
+        .in_room(lab.clone());
+    let err = manager.add_event(clash.clone(), ConflictPolicy::Allow).unwrap_err();
+    assert_eq!(err.downcast::<RoomConflict>().unwrap().conflicts[0].name, "Physics lab");
+    manager.add_event(NewWeekEvent { room: None, ..clash }, ConflictPolicy::Allow).unwrap();
+    let chemistry = manager.by_name("Chemistry lab".into()).unwrap().remove(0);
+    let lab_id = manager.get_rooms().unwrap().into_iter().find(|r| r.same_as(&lab)).unwrap().id;
+    let move_in = UpdatedWeekEvent { id: chemistry.id.unwrap(), room_id: Some(lab_id), ..Default::default() };
+    assert!(manager.change_event(move_in.clone(), ConflictPolicy::Allow).is_err());
+    //the room is free in the afternoon
+    manager.change_event(UpdatedWeekEvent { starth: Some("13:00:00".into()), endh: Some("14:00:00".into()), ..move_in },
+        ConflictPolicy::Allow).unwrap();
+    assert_eq!(manager.by_room("C-13 2.41").unwrap().len(), 2);
+
+    assert!("C-13".parse::<Room>().is_err());
+    manager.delete_room(lab_id.unwrap()).unwrap();
+    assert!(manager.get_event(chemistry.id.unwrap()).unwrap().room.is_none());
+
+    //rooms of rejected changes and events aren't stored
+    let rooms = manager.get_rooms().unwrap().len();
+    let move_out = UpdatedWeekEvent { id: chemistry.id.unwrap(), room: Some(Room::new("B-2", "7")), ..Default::default() };
+    //ends before it starts
+    assert!(manager.change_event(UpdatedWeekEvent { starth: Some("15:00:00".into()), ..move_out.clone() },
+        ConflictPolicy::Allow).is_err());
+    let overlap = NewWeekEvent::new("Seminar".into(), Weekday::Mon, "10:30:00", "11:00:00", EventKind::Seminar)
+        .in_room(Room::new("B-2", "8"));
+    assert!(manager.add_event(overlap, ConflictPolicy::Reject).is_err());
+    assert_eq!(manager.get_rooms().unwrap().len(), rooms);
+    manager.change_event(move_out, ConflictPolicy::Allow).unwrap();
+    assert_eq!(manager.by_room("B-2 7").unwrap()[0].name, "Chemistry lab");
+}
+
+#[test]
+fn instructors() {
+    use chrono::Weekday;
+    use super::{NewWeekEvent, EventKind, Instructor, Recurrence};
+
+    let mut manager = memory_manager();
+    manager.add_events(vec![
+        NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:00:00", "11:30:00", EventKind::Lecture),
+        NewWeekEvent::new("Algebra exercises".into(), Weekday::Tue, "10:00:00", "12:00:00", EventKind::Exercise)
+            .with_recurrence(Recurrence::Odd),
+        NewWeekEvent::new("Physics".into(), Weekday::Wed, "10:00:00", "11:00:00", EventKind::Lecture),
+    ], ConflictPolicy::Reject).unwrap();
+    let nowak = manager.add_instructor(Instructor {
+        email: Some("nowak@uni.edu".into()),
+        ..Instructor::new("Anna Nowak")
+    }).unwrap();
+    let kowalski = manager.add_instructor(Instructor::new("Jan Kowalski")).unwrap();
+    //adding again updates the contact info instead of duplicating
+    manager.add_instructor(Instructor { office: Some("B-2 12".into()), ..Instructor::new("Anna Nowak") }).unwrap();
+    assert_eq!(manager.get_instructors().unwrap().len(), 2);
+    assert_eq!(manager.get_instructor("anna nowak").unwrap().office.as_deref(), Some("B-2 12"));
+
+    let (algebra, exercises, physics) = (id_of(&mut manager, "Algebra"), 
+        id_of(&mut manager, "Algebra exercises"), id_of(&mut manager, "Physics"));
+    manager.assign_instructor(algebra, nowak.id.unwrap()).unwrap();
+    manager.assign_instructor(exercises, nowak.id.unwrap()).unwrap();
+    manager.assign_instructor(physics, kowalski.id.unwrap()).unwrap();
+    manager.assign_instructor(physics, nowak.id.unwrap()).unwrap();
+    manager.assign_instructor(physics, nowak.id.unwrap()).unwrap();
+
+    let mut taught = manager.by_instructor("nowak").unwrap()
+        .into_iter()
+        .map(|e| e.name)
+        .collect::<Vec<String>>();
+    taught.sort();
+    assert_eq!(taught, vec!["Algebra", "Algebra exercises", "Physics"]);
+    let physics_event = manager.get_event(physics).unwrap();
+    assert_eq!(physics_event.instructors.len(), 2);
+    assert!(physics_event.to_string().contains("Instructors: Anna Nowak, Jan Kowalski\n"));
+
+    //1.5h weekly lecture, 2h exercises every other week and 1h of physics
+    let load = manager.by_instructor("Anna Nowak").unwrap()
+        .iter()
+        .map(|e| e.weekly_hours())
+        .sum::<f64>();
+    assert_eq!(load, 3.5);
+
+    assert!(manager.unassign_instructor(physics, nowak.id.unwrap()).unwrap());
+    assert!(!manager.unassign_instructor(physics, nowak.id.unwrap()).unwrap());
+    manager.delete_event(physics).unwrap();
+    assert!(manager.by_instructor("Kowalski").unwrap().is_empty());
+    assert!(manager.get_instructor("Nobody").is_err());
+}
+
+#[test]
+fn courses() {
+    use chrono::Weekday;
+    use super::{NewWeekEvent, EventKind, Course, Recurrence, ValidationError, models::UpdatedWeekEvent};
+
+    let mut manager = memory_manager();
+    let algebra = manager.add_course(Course { ects: 5.0, colour: Some("#ff8000".into()), ..Course::new("MAT101", "Algebra") }).unwrap();
+    manager.add_events(vec![
+        NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:00:00", "11:30:00", EventKind::Lecture)
+            .of_course(Course::new("mat101", "")),
+        NewWeekEvent::new("Algebra exercises".into(), Weekday::Tue, "10:00:00", "12:00:00", EventKind::Exercise)
+            .with_recurrence(Recurrence::Odd),
+        NewWeekEvent::new("Physics".into(), Weekday::Wed, "10:00:00", "11:00:00", EventKind::Lecture),
+    ], ConflictPolicy::Reject).unwrap();
+    assert!(manager.add_event(NewWeekEvent::new("Chemistry".into(), Weekday::Thu, "10:00:00", "11:00:00", EventKind::Lecture)
+        .of_course(Course::new("CHE101", "")), ConflictPolicy::Reject).is_err());
+
+    let exercises = manager.by_name("Algebra exercises".into()).unwrap()[0].id.unwrap();
+    manager.change_event(UpdatedWeekEvent {
+        id: exercises,
+        course_id: Some(algebra.id),
+        ..Default::default()
+    }, ConflictPolicy::Reject).unwrap();
+    //renaming a session doesn't take it out of the course
+    manager.change_event(UpdatedWeekEvent {
+        id: exercises,
+        name: Some("Tutorial".into()),
+        ..Default::default()
+    }, ConflictPolicy::Reject).unwrap();
+
+    let sessions = manager.by_course("MAT101").unwrap();
+    assert_eq!(sessions.iter().map(|e| e.name.as_str()).collect::<Vec<&str>>(), vec!["Algebra", "Tutorial"]);
+    assert_eq!(sessions.iter().map(|e| e.weekly_hours()).sum::<f64>(), 2.5);
+    assert!(sessions[0].to_string().contains("Course: MAT101 Algebra\n"));
+    assert_eq!(sessions[0].course.as_ref().unwrap().color().as_deref(), Some("38;2;255;128;0"));
+
+    assert_eq!(manager.link_course(algebra.id.unwrap(), "Physics").unwrap(), 1);
+    assert_eq!(manager.by_course("mat101").unwrap().len(), 3);
+
+    assert_eq!(manager.add_course(Course { colour: Some("orange".into()), ..Course::new("PHY101", "Physics") })
+        .unwrap_err().downcast::<ValidationError>().unwrap(), ValidationError::InvalidColour("orange".into()));
+    assert!(manager.add_course(Course { ects: -1.0, ..Course::new("PHY101", "Physics") }).is_err());
+
+    manager.delete_course(algebra.id.unwrap()).unwrap();
+    assert!(manager.get_courses().unwrap().is_empty());
+    assert!(manager.get_all().unwrap().iter().all(|e| e.course.is_none()));
+    assert_eq!(manager.get_all().unwrap().len(), 3);
+}
+
+#[test]
+fn tags_and_notes() {
+    use chrono::Weekday;
+    use super::{NewWeekEvent, EventKind, Note, ValidationError};
+
+    let mut manager = memory_manager();
+    manager.add_events(vec![
+        NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:00:00", "11:30:00", EventKind::Lecture),
+        NewWeekEvent::new("Programming lab".into(), Weekday::Tue, "10:00:00", "12:00:00", EventKind::Lab),
+    ], ConflictPolicy::Reject).unwrap();
+    let (algebra, lab) = (id_of(&mut manager, "Algebra"), id_of(&mut manager, "Programming lab"));
+
+    assert!(manager.add_tag(lab, "bring laptop").unwrap());
+    assert!(manager.add_tag(lab, "Online this week").unwrap());
+    assert!(!manager.add_tag(lab, " Bring Laptop ").unwrap());
+    assert!(manager.add_tag(algebra, "online this week").unwrap());
+    assert_eq!(manager.add_tag(algebra, "  ").unwrap_err().downcast::<ValidationError>().unwrap(),
+        ValidationError::EmptyText);
+    assert!(manager.add_tag(1000, "missing").is_err());
+
+    assert_eq!(manager.get_event(lab).unwrap().tags, vec!["bring laptop", "Online this week"]);
+    let online = manager.by_tag("ONLINE THIS WEEK").unwrap()
+        .into_iter()
+        .map(|e| e.name)
+        .collect::<Vec<String>>();
+    assert_eq!(online, vec!["Algebra", "Programming lab"]);
+
+    assert!(manager.remove_tag(algebra, "Online this week").unwrap());
+    assert!(!manager.remove_tag(algebra, "online this week").unwrap());
+    assert_eq!(manager.by_tag("online this week").unwrap().len(), 1);
+
+    let note = manager.add_note(Note::new(lab, "Room changes to B-2 12\nbring your student card")).unwrap();
+    manager.add_note(Note::new(lab, "Exam next week")).unwrap();
+    assert!(manager.add_note(Note::new(lab, "\n")).is_err());
+    let lab_event = manager.get_event(lab).unwrap();
+    assert_eq!(lab_event.notes.len(), 2);
+    assert_eq!(lab_event.notes[0], note);
+    assert!(lab_event.to_string().contains("Tags: bring laptop, Online this week\n"));
+    assert!(lab_event.to_string().contains(&format!("Notes:\n  [{}]\n    Room changes to B-2 12\n    bring your student card\n", note.id.unwrap())));
+
+    assert!(manager.remove_note(note.id.unwrap()).unwrap());
+    assert!(!manager.remove_note(note.id.unwrap()).unwrap());
+    manager.delete_event(lab).unwrap();
+    assert!(manager.by_tag("bring laptop").unwrap().is_empty());
+    assert!(manager.get_event(algebra).unwrap().notes.is_empty());
+}
+
+#[test]
+fn timezones() {
+    use super::timezone;
+    use chrono_tz::Tz;
+
+    let mut manager = memory_manager();
+    assert_eq!(manager.timezone().unwrap(), timezone::system());
+    manager.set_timezone(Tz::Pacific__Kiritimati).unwrap();
+    assert_eq!(manager.timezone().unwrap(), Tz::Pacific__Kiritimati);
+    let ahead = manager.today().unwrap();
+    //UTC+14 and UTC-11 are never on the same day
+    manager.set_timezone(Tz::Pacific__Pago_Pago).unwrap();
+    assert_eq!(manager.timezone().unwrap(), Tz::Pacific__Pago_Pago);
+    assert!(manager.today().unwrap() < ahead);
+    assert_eq!(manager.now().unwrap().timezone(), Tz::Pacific__Pago_Pago);
+    //the configured timezone doesn't override one that was set
+    manager.seed_timezone(Tz::Europe__Warsaw).unwrap();
+    assert_eq!(manager.timezone().unwrap(), Tz::Pacific__Pago_Pago);
+    let mut fresh = memory_manager();
+    fresh.seed_timezone(Tz::Europe__Warsaw).unwrap();
+    assert_eq!(fresh.timezone().unwrap(), Tz::Europe__Warsaw);
+
+    assert_eq!(timezone::parse(" Europe/Warsaw ").unwrap(), Tz::Europe__Warsaw);
+    assert!(timezone::parse("Mars/Olympus_Mons").is_err());
+}
+
+#[test]
+fn now_and_upcoming() {
+    use chrono::{NaiveDate, Weekday};
+    use super::{NewWeekEvent, EventKind, Recurrence, Holiday, EventException, Change};
+
+    let mut manager = memory_manager();
+    //added out of order, so `upcoming` has to sort them
+    manager.add_events(vec![
+        NewWeekEvent::new("Physics".into(), Weekday::Mon, "14:00:00", "15:30:00", EventKind::Lecture),
+        NewWeekEvent::new("Algebra".into(), Weekday::Mon, "10:00:00", "11:30:00", EventKind::Lecture),
+        NewWeekEvent::new("Programming lab".into(), Weekday::Wed, "08:00:00", "10:00:00", EventKind::Lab)
+            .with_recurrence(Recurrence::Odd),
+    ], ConflictPolicy::Reject).unwrap();
+    //2023-01-09 is a Monday in an even week
+    let monday = NaiveDate::from_ymd_opt(2023, 1, 9).unwrap();
+    let at = |d: NaiveDate, t: &str| d.and_time(t.parse().unwrap());
+
+    let now = manager.happening_at(at(monday, "10:30:00")).unwrap();
+    assert_eq!(now.iter().map(|e| e.name.as_str()).collect::<Vec<&str>>(), vec!["Algebra"]);
+    assert!(manager.happening_at(at(monday, "11:30:00")).unwrap().is_empty());
+
+    let names = |u: Vec<(NaiveDate, super::WeekEvent)>| u.into_iter()
+        .map(|(d, e)| (d.to_string(), e.name))
+        .collect::<Vec<(String, String)>>();
+    assert_eq!(names(manager.upcoming(at(monday, "09:00:00"), 2).unwrap()), vec![
+        ("2023-01-09".to_owned(), "Algebra".to_owned()),
+        ("2023-01-09".to_owned(), "Physics".to_owned()),
+    ]);
+    //after the last class of the day the next ones are on the following days, 
+    // skipping the lab in the even week
+    assert_eq!(names(manager.upcoming(at(monday, "16:00:00"), 3).unwrap()), vec![
+        ("2023-01-16".to_owned(), "Algebra".to_owned()),
+        ("2023-01-16".to_owned(), "Physics".to_owned()),
+        ("2023-01-18".to_owned(), "Programming lab".to_owned()),
+    ]);
+    assert!(memory_manager().upcoming(at(monday, "09:00:00"), 1).unwrap().is_empty());
+
+    //holidays and cancellations are skipped as well
+    let next_monday = NaiveDate::from_ymd_opt(2023, 1, 16).unwrap();
+    manager.add_holiday(Holiday { id: None, date: next_monday, name: "Day off".into() }).unwrap();
+    let lab = manager.by_name("Programming lab".into()).unwrap()[0].id.unwrap();
+    let wednesday = NaiveDate::from_ymd_opt(2023, 1, 18).unwrap();
+    manager.add_exception(EventException { id: None, event_id: lab, date: wednesday, change: Change::Cancelled }).unwrap();
+    assert_eq!(names(manager.upcoming(at(monday, "16:00:00"), 1).unwrap()), vec![
+        ("2023-01-23".to_owned(), "Algebra".to_owned()),
+    ]);
+}
+
+#[test]
+fn named_arguments() {
+    let mut handler = handler_with::<test_commands::FlagModule>();
+    assert_eq!(message(&mut handler, "echo 5").unwrap(), "5 None None false false");
+    assert_eq!(message(&mut handler, "echo 5 --endh 12:00").unwrap(), "5 Some(12:00:00) None false false");
+    assert_eq!(message(&mut handler, "echo --new-name=Algebra -fl 5 -e 12:00").unwrap(), "5 Some(12:00:00) Some(\"Algebra\") true true");
+    assert_eq!(message(&mut handler, "echo --force -- -5").unwrap(), "-5 None None true false");
+    assert_eq!(message(&mut handler, "echo -5").unwrap(), "-5 None None false false");
+    assert_eq!(message(&mut handler, "echo 5 --room A").unwrap_err(), "unknown argument `--room`");
+    assert_eq!(message(&mut handler, "echo 5 --endh").unwrap_err(), "`--endh` needs a value");
+    assert_eq!(message(&mut handler, "echo 5 -ef 12:00").unwrap_err(), "`-e` takes a value, so it has to come last in `-ef`");
+    assert!(message(&mut handler, "echo 5 6").is_err());
+
+    let info = handler.commands_info();
+    let usage = info[0].args.iter().map(|a| a.to_string()).collect::<Vec<String>>();
+    assert_eq!(usage, vec!["id: i32", "[-e|--endh <Option<chrono::NaiveTime>>]", 
+        "[--new-name <Option<String>>]", "[-f|--force]", "[-l|--long]"]);
+}
+
+#[test]
+fn default_arguments() {
+    let mut handler = handler_with::<test_commands::DefaultModule>();
+    assert_eq!(message(&mut handler, "echo 1").unwrap(), "1 None 3 08:00:00 None weekly");
+    assert_eq!(message(&mut handler, "echo 1 Mon").unwrap(), "1 Some(Mon) 3 08:00:00 None weekly");
+    assert_eq!(message(&mut handler, "echo 1 ! 5 09:30 10:00 --recurrence odd").unwrap(), "1 None 5 09:30:00 Some(10:00:00) odd");
+    assert_eq!(message(&mut handler, "echo 1 Mon ! 10:00").unwrap(), "1 Some(Mon) 3 10:00:00 None weekly");
+    assert_eq!(message(&mut handler, "echo").unwrap_err(), "invalid argument count! expected 1 to 5, got 0");
+    assert!(message(&mut handler, "echo 1 Mon 1 10:00 11:00 12:00").is_err());
+
+    let info = handler.commands_info();
+    let usage = info[0].args.iter().map(|a| a.to_string()).collect::<Vec<String>>();
+    assert_eq!(usage, vec!["id: i32", "[day: Option<chrono::Weekday>]", "[amount: u32 = 3]", "[from: chrono::NaiveTime = 08:00]",
+        "[until: Option<chrono::NaiveTime>]", "[--recurrence <crate::Recurrence> = weekly]"]);
+    assert!(!info[0].args[0].optional);
+    assert!(info[0].args[1].optional);
+    assert_eq!(info[0].args[2].default.as_deref(), Some("3"));
+}
+
+#[test]
+fn rest_arguments() {
+    let mut handler = handler_with::<test_commands::RestModule>();
+    assert_eq!(message(&mut handler, "sum total 3 7 12").unwrap(), "total 22");
+    assert_eq!(message(&mut handler, "sum total 5").unwrap(), "total 5");
+    assert_eq!(message(&mut handler, "sum -n total 1 2").unwrap(), "total -3");
+    assert_eq!(message(&mut handler, "sum total").unwrap_err(), "invalid argument count! expected at least 2, got 1");
+    assert!(message(&mut handler, "sum total 1 x").is_err());
+    assert_eq!(message(&mut handler, "days Mon Wed Fri").unwrap(), "[Mon, Wed, Fri]");
+
+    let info = handler.commands_info();
+    let sum = info.iter().find(|c| c.name == "sum").unwrap();
+    let usage = sum.args.iter().map(|a| a.to_string()).collect::<Vec<String>>();
+    assert_eq!(usage, vec!["label: String", "values: i32...", "[-n|--negate]"]);
+}
+
+#[test]
+fn nested_groups() {
+    let mut handler = handler_with::<test_commands::CsvModule>();
+    assert!(handler.add_module::<test_commands::CsvModule>().is_err());
+    assert_eq!(handler.add_module::<test_commands::ClashModule>().unwrap_err().to_string(),
+        "`io csv` is a group, so it can't be a command too!");
+    assert_eq!(message(&mut handler, "io csv export out.csv").unwrap(), "export out.csv");
+    assert_eq!(message(&mut handler, "io csv set delimiter ;").unwrap(), "delimiter ;");
+    assert_eq!(message(&mut handler, "io csv").unwrap_err(), "No function specified");
+    assert_eq!(message(&mut handler, "io csv import in.csv").unwrap_err(), "Command not found!");
+    assert_eq!(message(&mut handler, "export out.csv").unwrap_err(), "Command not found!");
+
+    let tree = handler.commands();
+    assert_eq!(tree.groups(), vec!["io"]);
+    assert_eq!(tree.group(&["io".into(), "csv".into()]).unwrap().groups(), vec!["set"]);
+    let mut paths = handler.commands_info().into_iter()
+        .map(|c| format!("{} {}", c.group.join(" "), c.name))
+        .collect::<Vec<String>>();
+    paths.sort();
+    assert_eq!(paths, vec!["io csv export", "io csv set delimiter"]);
+
+    handler.remove_command("delimiter", &["io".into(), "csv".into(), "set".into()]).unwrap();
+    assert!(handler.execute("io csv set delimiter ;".into()).is_err());
+}
+
+#[test]
+fn help() {
+    use super::commands::*;
+
+    let mut handler = handler_with::<test_commands::CsvModule>();
+    handler.add_module::<test_commands::DefaultModule>().unwrap();
+
+    let usage = "Usage: echo <id> [<day>] [<amount>] [<from>] [<until>] [--recurrence <recurrence>]\nEchoes its arguments.";
+    for input in ["help echo", "echo --help", "echo 5 --help"] {
+        match handler.execute(input.into()).unwrap() {
+            CommandOutput::Many(outputs) => {
+                assert!(matches!(&outputs[0], CommandOutput::Message(m) if m == usage));
+                match &outputs[1] {
+                    CommandOutput::Table { headers, rows } => {
+                        assert_eq!(headers, &vec!["argument", "type", "default"]);
+                        assert_eq!(rows.len(), 6);
+                        assert_eq!(rows[0], vec!["<id>", "i32", ""]);
+                        assert_eq!(rows[2], vec!["[<amount>]", "u32", "3"]);
+                        assert_eq!(rows[5][2], "weekly");
+                    },
+                    other => panic!("unexpected output {:?}", other),
+                }
+            },
+            other => panic!("unexpected output {:?}", other),
+        }
+    }
+
+    let commands = |output: CommandOutput| match output {
+        CommandOutput::Table { rows, .. } => rows.into_iter().map(|r| r[0].clone()).collect::<Vec<String>>(),
+        other => panic!("unexpected output {:?}", other),
+    };
+    assert_eq!(commands(handler.execute("help".into()).unwrap()),
+        vec!["echo <id> [<day>] [<amount>] [<from>] [<until>] [--recurrence <recurrence>]",
+            "io csv export <path>", "io csv set delimiter <delimiter>"]);
+    assert_eq!(commands(handler.execute("help io csv set".into()).unwrap()),
+        vec!["io csv set delimiter <delimiter>"]);
+    assert_eq!(commands(handler.execute("io --help".into()).unwrap()).len(), 2);
+    assert_eq!(handler.execute("help io json".into()).unwrap_err().to_string(),
+        "No command or group named `io json`");
+    assert!(matches!(handler.execute("help io csv export".into()).unwrap(), CommandOutput::Many(_)));
+    //after `--` it's just a value
+    assert!(matches!(handler.execute("io csv export -- --help".into()).unwrap(),
+        CommandOutput::Message(m) if m == "export --help"));
+}
@@ -0,0 +1,28 @@
+use chrono_tz::Tz;
+use anyhow::{Result, anyhow};
+
+///Environment variable, or `.env` entry, naming the timezone of the timetable, e.g. `Europe/Warsaw`.
+/// It is only stored when the timetable has no timezone yet, afterwards the `timezone` command changes it.
+pub const TIMEZONE_VAR: &str = "SYD_TIMEZONE";
+
+///Parses an IANA timezone name, e.g. `Europe/Warsaw` or `UTC`.
+pub fn parse(name: &str) -> Result<Tz> {
+    name.trim().parse::<Tz>()
+        .map_err(|_| anyhow!("`{}` is not a timezone, expected a name like `Europe/Warsaw`", name.trim()))
+}
+
+///The timezone set through `TIMEZONE_VAR`, if any.
+pub fn configured() -> Result<Option<Tz>> {
+    match std::env::var(TIMEZONE_VAR) {
+        Ok(name) if !name.trim().is_empty() => Ok(Some(parse(&name)?)),
+        _ => Ok(None),
+    }
+}
+
+///The system's local timezone, UTC if it can't be found.
+pub fn system() -> Tz {
+    iana_time_zone::get_timezone()
+        .ok()
+        .and_then(|name| parse(&name).ok())
+        .unwrap_or(Tz::UTC)
+}
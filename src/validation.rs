@@ -0,0 +1,166 @@
+use std::fmt::Display;
+use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
+use super::{WeekEvent, NewWeekEvent, NewTerm, EventException, Change, Holiday, Room, Instructor, Course, Note};
+
+///Returned when an event can't be stored because its fields don't make sense.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    EmptyName,
+    ///The event ends before, or at the same time as, it starts.
+    InvalidRange {
+        starth: NaiveTime,
+        endh: NaiveTime,
+    },
+    ///A one-off event's day doesn't match the weekday of its date.
+    WrongWeekday {
+        date: NaiveDate,
+        day: Weekday,
+    },
+    ///A room is missing its building or number.
+    IncompleteRoom,
+    ///A tag or note is blank.
+    EmptyText,
+    ///A course's colour isn't a `#rrggbb` hex color.
+    InvalidColour(String),
+    ///A course's ECTS credits are negative.
+    NegativeEcts(f64),
+    ///The term ends before it starts.
+    InvalidTermRange {
+        start: NaiveDate,
+        end: NaiveDate,
+    },
+    ///A range of holidays ends before it starts.
+    InvalidHolidayRange {
+        start: NaiveDate,
+        end: NaiveDate,
+    },
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::EmptyName => write!(f, "name can't be empty"),
+            ValidationError::InvalidRange { starth, endh } =>
+                write!(f, "event must end after it starts (starts at {}, ends at {})", starth, endh),
+            ValidationError::WrongWeekday { date, day } =>
+                write!(f, "{} is a {}, not a {}", date, date.weekday(), day),
+            ValidationError::IncompleteRoom => write!(f, "room needs both a building and a number"),
+            ValidationError::EmptyText => write!(f, "tags and notes can't be empty"),
+            ValidationError::InvalidColour(c) => write!(f, "`{}` is not a colour, expected `#rrggbb`", c),
+            ValidationError::NegativeEcts(e) => write!(f, "ECTS credits can't be negative ({})", e),
+            ValidationError::InvalidTermRange { start, end } =>
+                write!(f, "term can't end before it starts (starts on {}, ends on {})", start, end),
+            ValidationError::InvalidHolidayRange { start, end } =>
+                write!(f, "holidays can't end before they start (start on {}, end on {})", start, end),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+///Checks the fields shared by all kinds of events.
+pub fn validate(name: &str, starth: NaiveTime, endh: NaiveTime) -> Result<(), ValidationError> {
+    if name.trim().is_empty() {
+        return Err(ValidationError::EmptyName);
+    }
+    if endh <= starth {
+        return Err(ValidationError::InvalidRange { starth, endh });
+    }
+    Ok(())
+}
+
+///Checks that a one-off event's date falls on its `day`.
+pub fn validate_date(date: Option<NaiveDate>, day: Weekday) -> Result<(), ValidationError> {
+    match date {
+        Some(date) if date.weekday() != day => Err(ValidationError::WrongWeekday { date, day }),
+        _ => Ok(()),
+    }
+}
+
+impl NewWeekEvent {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        validate(&self.name, self.starth, self.endh)?;
+        validate_date(self.date, self.day)
+    }
+}
+
+impl WeekEvent {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        validate(&self.name, self.starth, self.endh)?;
+        validate_date(self.date, self.day)
+    }
+}
+
+impl NewTerm {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.name.trim().is_empty() {
+            return Err(ValidationError::EmptyName);
+        }
+        if self.end < self.start {
+            return Err(ValidationError::InvalidTermRange { start: self.start, end: self.end });
+        }
+        Ok(())
+    }
+}
+
+impl EventException {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if let Change::Moved { starth, endh, .. } = self.change {
+            if endh <= starth {
+                return Err(ValidationError::InvalidRange { starth, endh });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Holiday {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.name.trim().is_empty() {
+            return Err(ValidationError::EmptyName);
+        }
+        Ok(())
+    }
+}
+
+impl Room {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.building.trim().is_empty() || self.number.trim().is_empty() {
+            return Err(ValidationError::IncompleteRoom);
+        }
+        Ok(())
+    }
+}
+
+impl Instructor {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.name.trim().is_empty() {
+            return Err(ValidationError::EmptyName);
+        }
+        Ok(())
+    }
+}
+
+impl Note {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.text.trim().is_empty() {
+            return Err(ValidationError::EmptyText);
+        }
+        Ok(())
+    }
+}
+
+impl Course {
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.code.trim().is_empty() || self.title.trim().is_empty() {
+            return Err(ValidationError::EmptyName);
+        }
+        if self.ects < 0.0 {
+            return Err(ValidationError::NegativeEcts(self.ects));
+        }
+        match &self.colour {
+            Some(c) if self.color().is_none() => Err(ValidationError::InvalidColour(c.clone())),
+            _ => Ok(()),
+        }
+    }
+}
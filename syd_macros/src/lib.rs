@@ -41,43 +41,111 @@ fn impl_command(function: &syn::ImplItemMethod) -> TokenStream {
         panic!("First argument must be of type `&mut CommandContext`");
     }
 
-    //check if method returns Result<()>
+    //check if method returns Result<CommandOutput>
     let output = &function.sig.output;
     let syn::ReturnType::Type(_, a) = output else { 
-        panic!("The return type of a command must be `Result<()>"); 
+        panic!("The return type of a command must be `Result<CommandOutput>"); 
     };
     let syn::Type::Path(path) = &**a else { 
-        panic!("The return type of a command must be `Result<()>"); 
+        panic!("The return type of a command must be `Result<CommandOutput>"); 
     };
     let Some(seg) = path.path.segments.last() else { 
-        panic!("The return type of a command must be `Result<()>"); 
+        panic!("The return type of a command must be `Result<CommandOutput>"); 
     };
     if seg.ident != "Result" { 
-        panic!("The return type of a command must be `Result<()>"); 
+        panic!("The return type of a command must be `Result<CommandOutput>"); 
     };
 
     let syn::PathArguments::AngleBracketed(bracketed) =
         &seg.arguments else { 
-            panic!("The return type of a command must be `Result<()>"); 
+            panic!("The return type of a command must be `Result<CommandOutput>"); 
         };
     let Some(syn::GenericArgument::Type(gen_ty)) = 
         bracketed.args.first() else { 
-            panic!("The return type of a command must be `Result<()>"); 
+            panic!("The return type of a command must be `Result<CommandOutput>"); 
         };
-    if let syn::Type::Path(_path) = gen_ty {
-        panic!("The return type of a command must be `Result<()>");
-    } 
+    let syn::Type::Path(gen_path) = gen_ty else {
+        panic!("The return type of a command must be `Result<CommandOutput>");
+    };
+    if gen_path.path.segments.last().unwrap().ident != "CommandOutput" {
+        panic!("The return type of a command must be `Result<CommandOutput>");
+    }
+    let params = get_params(inputs);
     let name = &function.sig.ident;
+    let args_ident: syn::Ident = syn::parse_str(&format!("{}_args", name)).unwrap();
     let name = format!("{}_parse", name);
     let name: syn::Ident = syn::parse_str(&name).unwrap();
-    let arg_count = inputs.len() - 1;
+
+    let mut arg_decls: Vec<syn::Expr> = vec![];
+    for param in &params {
+        let arg_name = param.pat;
+        //rest arguments are shown by the type of a single value
+        let ty = rest_item(param.ty).filter(|_| param.style == ArgStyle::Rest)
+            .unwrap_or(param.ty);
+        let ty = quote!(#ty).to_string().replace(' ', "");
+        let style: syn::Ident = syn::parse_str(match param.style {
+            ArgStyle::Positional => "Positional",
+            ArgStyle::Rest => "Rest",
+            ArgStyle::Flag => "Flag",
+            ArgStyle::Named => "Named",
+        }).unwrap();
+        let short = match param.short {
+            Some(c) => quote!{Some(#c)},
+            None => quote!{None},
+        };
+        let optional = param.optional;
+        let default = match &param.default {
+            Some(syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(l), .. })) => {
+                let value = l.value();
+                quote!{Some(#value.to_owned())}
+            },
+            Some(d) => {
+                let value = quote!(#d).to_string();
+                quote!{Some(#value.to_owned())}
+            },
+            None => quote!{None},
+        };
+        arg_decls.push(syn::parse_quote!{
+            syd::commands::CommandArg {
+                name: stringify!(#arg_name).to_owned(),
+                ty: #ty.to_owned(),
+                style: syd::commands::ArgStyle::#style,
+                short: #short,
+                optional: #optional,
+                default: #default,
+            }
+        });
+    }
+    let args_method: syn::ImplItemMethod = syn::parse_quote!{
+        #[doc(hidden)]
+        pub fn #args_ident() -> Vec<syd::commands::CommandArg> {
+            vec![#(#arg_decls),*]
+        }
+    };
+
+    let positional = params.iter()
+        .filter(|p| p.is_positional());
+    //a rest argument takes any number of values, so there's no upper bound
+    let max_count = match positional.clone().any(|p| p.style == ArgStyle::Rest) {
+        true => usize::MAX,
+        false => positional.clone().count(),
+    };
+    let min_count = positional.filter(|p| !p.optional).count();
+    let expected = match max_count {
+        usize::MAX => format!("at least {}", min_count),
+        max if max == min_count => min_count.to_string(),
+        max => format!("{} to {}", min_count, max),
+    };
     let mut parse_method: syn::ImplItemMethod = syn::parse_quote!{
         #[doc(hidden)]
-        pub fn #name (context: &mut CommandContext, args: Vec<String>) -> Result<()> {
+        pub fn #name (context: &mut CommandContext, args: Vec<String>) 
+            -> Result<syd::commands::CommandOutput> {
             use anyhow::anyhow;
             use syd::commands::ArgParse;
-            if args.len() != #arg_count {
-                return Err(anyhow!("invalid argument count!"));
+            let args = syd::commands::ArgMatches::new(args, &Self::#args_ident())?;
+            let count = args.positional().len();
+            if !(#min_count..=#max_count).contains(&count) {
+                return Err(anyhow!("invalid argument count! expected {}, got {}", #expected, count));
             }  
         }
     };
@@ -91,35 +159,210 @@ fn impl_command(function: &syn::ImplItemMethod) -> TokenStream {
     };
     caller.args.push(syn::parse_quote!{context});
 
-    for (n,i) in inputs.iter().skip(1).enumerate() {
-        let syn::FnArg::Typed(pat) = i else {panic!("adadada")};
-        let syn::Type::Path(path) = &*pat.ty else {panic!("adad")};
+    let mut position = 0usize;
+    for (n, param) in params.iter().enumerate() {
+        let ty = param.ty;
         let arg: syn::Ident = syn::parse_str(&format!("arg{}", n)).unwrap();
-        let stmt: syn::Stmt = syn::parse_quote!{
-            let #arg = <#path as ArgParse>::arg_parse(&args[#n])?;
+        if param.style == ArgStyle::Rest {
+            let item = rest_item(ty).unwrap();
+            let index = position;
+            parse_method.block.stmts.push(syn::parse_quote!{
+                let #arg = args.positional()[#index..].iter()
+                    .map(|a| <#item as ArgParse>::arg_parse(a))
+                    .collect::<anyhow::Result<#ty>>()?;
+            });
+            caller.args.push(syn::parse_quote!{ #arg });
+            continue;
+        }
+        let source: syn::Expr = match param.style {
+            ArgStyle::Positional => {
+                position += 1;
+                let index = position - 1;
+                syn::parse_quote!{ args.positional().get(#index).map(|a| a.as_str()) }
+            },
+            _ => {
+                let arg_name = param.pat;
+                syn::parse_quote!{ args.named(stringify!(#arg_name)) }
+            },
+        };
+        //string literals are parsed like typed arguments, other defaults are used as they are
+        let stmt: syn::Stmt = match &param.default {
+            Some(syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(l), .. })) => syn::parse_quote!{
+                let #arg = <#ty as ArgParse>::arg_parse(match #source {
+                    None | Some("!") => #l,
+                    Some(a) => a,
+                })?;
+            },
+            Some(d) => syn::parse_quote!{
+                let #arg: #ty = match #source {
+                    None | Some("!") => #d,
+                    Some(a) => <#ty as ArgParse>::arg_parse(a)?,
+                };
+            },
+            None => syn::parse_quote!{
+                let #arg = <#ty as ArgParse>::arg_parse(#source.unwrap_or_default())?;
+            },
         };
         parse_method.block.stmts.push(stmt);
         caller.args.push(syn::parse_quote!{ #arg });
     };
-    parse_method.block.stmts.push(syn::parse_quote!{#caller?;});
-    parse_method.block.stmts.push(syn::parse_quote!{ return Ok(()); });
+    parse_method.block.stmts.push(syn::parse_quote!{ return #caller; });
+
+    //the compiler doesn't know the parameter attributes, so they can't be left in
+    let mut function = function.clone();
+    for input in function.sig.inputs.iter_mut() {
+        if let syn::FnArg::Typed(t) = input {
+            t.attrs.retain(|a| !is_param_attribute(a));
+        }
+    }
 
     quote!{
         #function
+        #args_method
         #parse_method
     }.into()
     
 }
 
+#[derive(PartialEq)]
+enum ArgStyle {
+    Positional,
+    ///A final `Vec<T>` parameter taking the remaining positional arguments.
+    Rest,
+    Flag,
+    Named,
+}
+
+///A command parameter along with what its attributes declare it as.
+struct Param<'a> {
+    pat: &'a syn::Pat,
+    ty: &'a syn::Type,
+    style: ArgStyle,
+    short: Option<char>,
+    ///Whether the parameter can be left out, which flags and named ones always can.
+    optional: bool,
+    default: Option<syn::Expr>,
+}
+
+impl Param<'_> {
+    fn is_positional(&self) -> bool {
+        matches!(self.style, ArgStyle::Positional | ArgStyle::Rest)
+    }
+}
+
+const PARAM_ATTRIBUTES: [&str; 4] = ["flag", "option", "short", "default"];
+
+fn is_param_attribute(attr: &Attribute) -> bool {
+    PARAM_ATTRIBUTES.iter()
+        .any(|name| attr.path.segments.last().unwrap().ident == name)
+}
+
+///Reads the parameters of a command, skipping the `&mut CommandContext`.
+/// 
+/// `#[flag]` makes a `bool` parameter a `--name` flag, `#[option]` a `--name value` argument and 
+/// `#[short('n')]` gives either of them a `-n` alias. An `option` has to be an `Option<T>` or 
+/// have a default, since it can always be left out. `#[default(3)]` is used when the argument 
+/// is left out or given as `!`, a string literal is parsed like the argument would be.
+/// 
+/// Trailing positional parameters with a default or of type `Option<T>` may be left out, and
+/// the last positional one may be a `Vec<T>` that takes one or more of the remaining arguments.
+fn get_params(inputs: &Punctuated<syn::FnArg, syn::token::Comma>) -> Vec<Param<'_>> {
+    let mut ret = vec![];
+    for input in inputs.iter().skip(1) {
+        let syn::FnArg::Typed(t) = input else { panic!("commands can't take `self`") };
+        let has = |name: &str| t.attrs.iter()
+            .any(|a| a.path.segments.last().unwrap().ident == name);
+        let style = match (has("flag"), has("option")) {
+            (true, true) => panic!("a parameter can't be both a `flag` and an `option`"),
+            (true, false) => ArgStyle::Flag,
+            (false, true) => ArgStyle::Named,
+            (false, false) if rest_item(&t.ty).is_some() => ArgStyle::Rest,
+            (false, false) => ArgStyle::Positional,
+        };
+        let short = t.attrs.iter()
+            .find(|a| a.path.segments.last().unwrap().ident == "short")
+            .map(|a| match a.parse_args::<syn::LitChar>() {
+                Ok(c) => c.value(),
+                Err(_) => panic!("`short` takes a single character, e.g. `#[short('f')]`"),
+            });
+        if short.is_some() && matches!(style, ArgStyle::Positional | ArgStyle::Rest) {
+            panic!("only a `flag` or an `option` can have a `short` name");
+        }
+        let default = t.attrs.iter()
+            .find(|a| a.path.segments.last().unwrap().ident == "default")
+            .map(|a| match a.parse_args::<syn::Expr>() {
+                Ok(d) => d,
+                Err(_) => panic!("`default` takes the default value, e.g. `#[default(3)]`"),
+            });
+        if style == ArgStyle::Flag && !is_bool(&t.ty) {
+            panic!("a `flag` has to be a `bool`");
+        }
+        if default.is_some() && style == ArgStyle::Flag {
+            panic!("flags are `false` by default and can't have a `default`");
+        }
+        //named arguments can always be left out, so they need something to fall back on
+        if style == ArgStyle::Named && default.is_none() && !is_option(&t.ty) {
+            panic!("an `option` has to be an `Option<T>` or have a `default`");
+        }
+        if default.is_some() && style == ArgStyle::Rest {
+            panic!("a `Vec` parameter takes one or more values and can't have a `default`");
+        }
+        if ret.iter().any(|p: &Param| p.style == ArgStyle::Rest) && 
+            matches!(style, ArgStyle::Positional | ArgStyle::Rest) {
+            panic!("a `Vec` parameter must be the last positional one");
+        }
+        let optional = !matches!(style, ArgStyle::Positional | ArgStyle::Rest);
+        ret.push(Param { pat: &t.pat, ty: &t.ty, style, short, optional, default });
+    }
+    //only a run of positional parameters at the end can be left out
+    let mut trailing = true;
+    for param in ret.iter_mut().rev().filter(|p| p.is_positional()) {
+        trailing &= param.default.is_some() || is_option(param.ty);
+        if !trailing && param.default.is_some() {
+            panic!("positional parameters with a `default` must come after the required ones");
+        }
+        param.optional = trailing;
+    }
+    ret
+}
+
+///The item type of a `Vec<T>`.
+fn rest_item(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(p) = ty else { return None };
+    let last = p.path.segments.last()?;
+    if last.ident != "Vec" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(bracketed) = &last.arguments else { return None };
+    match bracketed.args.first()? {
+        syn::GenericArgument::Type(t) => Some(t),
+        _ => None,
+    }
+}
+
+fn is_bool(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(p) => p.path.is_ident("bool"),
+        _ => false,
+    }
+}
+
+fn is_option(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(p) => p.path.segments.last().unwrap().ident == "Option",
+        _ => false,
+    }
+}
+
 
 /// 
 /// Implements `CommandModule` for a struct, by registering certain methods as commands,
 /// must be used on its' `impl` block.
 /// 
 /// Will only register functions that are public, have a single `&mut CommandContext` argument
-/// and a return type of `anyhow::Result<()>`.
+/// and a return type of `anyhow::Result<CommandOutput>`.
 /// ```
-/// pub fn foo(context: &mut CommandContext) -> Result<()> {
+/// pub fn foo(context: &mut CommandContext) -> Result<CommandOutput> {
 ///     /.../
 /// }
 /// ```
@@ -129,9 +372,8 @@ fn impl_command(function: &syn::ImplItemMethod) -> TokenStream {
 ///pub struct TestModule;
 ///#[command_module]
 ///impl TestModule {
-///    pub fn test(context: &mut CommandContext) -> Result<()> {
-///        println!("Working!");
-///        Ok(())
+///    pub fn test(context: &mut CommandContext) -> Result<CommandOutput> {
+///        Ok(CommandOutput::message("Working!"))
 ///    }
 ///}
 /// ```
@@ -204,36 +446,33 @@ fn impl_command_module(ast: &syn::ItemImpl) -> TokenStream {
             }
         );
         //check if there is a group defined for these commands
-        let impl_group = get_group(&ast.attrs);
+        let impl_group = get_group(&ast.attrs).unwrap_or_default();
         
         let mut stmts = vec![];
         for m in methods {
             let path = &m.sig.ident;
 
-            let args = get_args(&m.sig.inputs);
             let description;
             if let Some(desc) = get_description(&m.attrs) {
                 description = quote!{Some(#desc.to_owned())};
             } else {
                 description = quote!{None};
             }
-            let group;
-            if let Some(g) = &impl_group {
-                group = quote!{Some(#g.to_owned())};
-            } else {
-                group = quote!{None};
-            }
+            //a command's own group is nested in the module's one
+            let mut groups = impl_group.clone();
+            groups.extend(get_group(&m.attrs).unwrap_or_default());
+            let group = quote!{vec![#(#groups.to_owned()),*]};
             let ident: syn::Ident = syn::parse_str(&format!("{}_parse", path)).unwrap();
+            let args_ident: syn::Ident = syn::parse_str(&format!("{}_args", path)).unwrap();
             let stmt: syn::Stmt = syn::parse_quote!{
                 commands.push( Command {
                     name: stringify!(#path).into(),
                     group: #group,
                     desc: #description,
-                    args: args,
+                    args: Self::#args_ident(),
                     function: Box::new(Self::#ident),
                 });
             };
-            stmts.extend(args);
             stmts.push(stmt);
         }
         init_method.block.stmts.extend(stmts);
@@ -263,7 +502,8 @@ fn impl_command_module(ast: &syn::ItemImpl) -> TokenStream {
     }
 }
 
-fn get_group(attrs: &Vec<Attribute>) -> Option<String> {
+///Reads the path of groups from a `command_group` attribute, e.g. `["io", "csv"]` from `"io csv"`.
+fn get_group(attrs: &Vec<Attribute>) -> Option<Vec<String>> {
     let valid = attrs.iter()
         .filter(|a| a.path.segments.last()
             .unwrap().ident == "command_group")
@@ -271,30 +511,14 @@ fn get_group(attrs: &Vec<Attribute>) -> Option<String> {
     if let Some(first) = valid.first() {
         let Ok(syn::Lit::Str(group)) 
             = first.parse_args() else { panic!("failed parsing command group")};
-        return Some(group.value());
+        return Some(group.value()
+            .split_whitespace()
+            .map(|g| g.to_owned())
+            .collect());
     }
     None
 }
 
-fn get_args(input: &Punctuated<syn::FnArg, syn::token::Comma>) -> Vec<syn::Stmt> {
-    let mut stmts: Vec<syn::Stmt> = vec![];
-    stmts.push(syn::parse_quote!{
-        let mut args: Vec<CommandArg> = vec![];
-    });
-    for arg in input.iter().skip(1) {
-        let syn::FnArg::Typed(t) = arg else { panic!("todo") };
-        let name = &*t.pat;
-        let ty = &*t.ty;
-        stmts.push(syn::parse_quote!{
-            args.push(CommandArg{
-                name: stringify!(#name).to_owned(),
-                ty: stringify!(#ty).to_owned(),
-            });
-        });
-    }
-    stmts
-}
-
 fn get_description(attrs: &Vec<Attribute>) -> Option<String> {
     let valid = attrs.iter()
         .filter(|a| a.path.segments.last()
@@ -339,15 +563,20 @@ fn impl_command_description(function: &syn::ItemFn, args: &Vec<syn::NestedMeta>)
     
 }
 
+///Puts commands in a group, given as one or more space separated names, e.g. `"io csv"`
+/// for commands called as `io csv <command>`.
+/// 
+/// Used on an `impl` block with `command_module` it applies to all of the commands in it, 
+/// used on a single command it nests that command further.
 #[proc_macro_attribute]
 pub fn command_group(args: TokenStream, item: TokenStream) -> TokenStream {
     let args = syn::parse_macro_input!(args as syn::AttributeArgs);
-    let item = syn::parse_macro_input!(item as syn::ItemImpl);
+    let item = syn::parse_macro_input!(item as syn::Item);
 
     impl_command_group(&item, &args)
 }
 
-fn impl_command_group(function: &syn::ItemImpl, args: &Vec<syn::NestedMeta>) -> TokenStream {
+fn impl_command_group(item: &syn::Item, args: &Vec<syn::NestedMeta>) -> TokenStream {
     if args.len() != 1 {
         panic!("the `command_group` macro must contain only one argument of type String");
     }
@@ -356,17 +585,20 @@ fn impl_command_group(function: &syn::ItemImpl, args: &Vec<syn::NestedMeta>) ->
     let syn::Lit::Str(lit) = nested else 
         { panic!("failed parsing attribute argument 2") };
     let group_name = lit.value();
-    if group_name.contains(' ') || !group_name.is_ascii() {
-        panic!("group name may only contain ascii characters and no spaces!")
+    if group_name.split_whitespace().next().is_none() || !group_name.is_ascii() {
+        panic!("group names may only contain ascii characters!")
     }
 
-
-    let attrs = &function.attrs;
+    let attrs = match item {
+        syn::Item::Impl(i) => &i.attrs,
+        syn::Item::Fn(f) => &f.attrs,
+        _ => panic!("`command_group` can only be used on a command or an `impl` block"),
+    };
     if attrs.iter()
         .any(|a| a.path.segments.last().unwrap().ident == "command_group") {
             panic!("this attribute can only be used once.")
     }
     quote!{
-        #function
+        #item
     }.into()
-}
\ No newline at end of file
+}